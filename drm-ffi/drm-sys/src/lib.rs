@@ -18,3 +18,15 @@ include!(concat!(
 pub const DRM_PLANE_TYPE_OVERLAY: u32 = 0;
 pub const DRM_PLANE_TYPE_PRIMARY: u32 = 1;
 pub const DRM_PLANE_TYPE_CURSOR: u32 = 2;
+
+// Unions cannot derive `Debug`. The request and reply start with the same
+// fields, which are the only ones valid in both.
+impl core::fmt::Debug for drm_wait_vblank {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let request = unsafe { &self.request };
+        f.debug_struct("drm_wait_vblank")
+            .field("type_", &request.type_)
+            .field("sequence", &request.sequence)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,43 @@
+//!
+//! Bindings to the dma-buf ioctls
+//!
+//! These are issued on dma-buf file descriptors, e.g. as exported by
+//! [`gem::handle_to_fd`](crate::gem::handle_to_fd), not on DRM devices.
+//!
+
+#![allow(non_camel_case_types)]
+
+use ioctl::{self, IoctlBackend};
+
+use result::SystemError as Error;
+
+/// Access the CPU is going to perform or has performed for reading
+pub const DMA_BUF_SYNC_READ: u64 = 1 << 0;
+/// Access the CPU is going to perform or has performed for writing
+pub const DMA_BUF_SYNC_WRITE: u64 = 1 << 1;
+/// Access the CPU is going to perform or has performed for reading and writing
+pub const DMA_BUF_SYNC_RW: u64 = DMA_BUF_SYNC_READ | DMA_BUF_SYNC_WRITE;
+/// CPU access starts
+pub const DMA_BUF_SYNC_START: u64 = 0 << 2;
+/// CPU access ends
+pub const DMA_BUF_SYNC_END: u64 = 1 << 2;
+
+/// Argument of `DMA_BUF_IOCTL_SYNC`
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct dma_buf_sync {
+    /// Combination of the `DMA_BUF_SYNC_*` flags
+    pub flags: u64,
+}
+
+/// Starts or ends CPU access to a mapped dma-buf, flushing or invalidating
+/// caches as needed.
+pub fn sync(fd: impl IoctlBackend, flags: u64) -> Result<dma_buf_sync, Error> {
+    let sync = dma_buf_sync { flags };
+
+    unsafe {
+        ioctl::dma_buf::sync(&fd, &sync)?;
+    }
+
+    Ok(sync)
+}
@@ -1,106 +1,275 @@
 #![allow(missing_docs)]
 
 use drm_sys::*;
+use nix::errno::Errno;
+use nix::libc::{c_int, c_void};
+use nix::sys::ioctl::ioctl_num_type;
+use std::os::unix::io::RawFd;
+
+/// Issues DRM ioctls.
+///
+/// All functions of this crate issue their ioctls through this trait. It is
+/// implemented for [`RawFd`] by calling into the kernel, but can also be
+/// implemented by a fake device returning canned data, e.g. for testing code
+/// without access to a GPU.
+///
+/// # Safety
+///
+/// The functions of this crate trust the data written by a backend like they
+/// trust the kernel. Implementations must behave like the kernel does:
+///
+/// - An array is only written if the count passed in is large enough to hold
+///   all entries, and the count written back never exceeds the number of
+///   initialized entries.
+/// - Object ids written back, e.g. the lessee id of a new lease, are nonzero
+///   on success.
+pub unsafe trait IoctlBackend {
+    /// Issues the ioctl `request` with `arg` pointing to its argument.
+    ///
+    /// Implementations can use [`command_number`] to tell requests apart.
+    ///
+    /// # Safety
+    ///
+    /// `arg` must be null or point to a valid value of the type expected by
+    /// `request`.
+    unsafe fn ioctl(&self, request: ioctl_num_type, arg: *mut c_void) -> nix::Result<c_int>;
+}
+
+unsafe impl IoctlBackend for RawFd {
+    unsafe fn ioctl(&self, request: ioctl_num_type, arg: *mut c_void) -> nix::Result<c_int> {
+        Errno::result(nix::libc::ioctl(*self, request, arg))
+    }
+}
+
+unsafe impl<B: IoctlBackend + ?Sized> IoctlBackend for &B {
+    unsafe fn ioctl(&self, request: ioctl_num_type, arg: *mut c_void) -> nix::Result<c_int> {
+        (**self).ioctl(request, arg)
+    }
+}
+
+/// Returns the DRM command number of an ioctl request, e.g. `0xA0` for
+/// `DRM_IOCTL_MODE_GETRESOURCES`.
+pub fn command_number(request: ioctl_num_type) -> u8 {
+    (request & 0xff) as u8
+}
+
+/// Logs an ioctl along with its argument after it was issued, if the `log`
+/// feature is enabled.
+#[cfg(feature = "log")]
+macro_rules! trace_ioctl {
+    ($name:ident, $data:expr, $result:expr) => {
+        log::trace!("{}: {:?} -> {:?}", stringify!($name), $data, $result)
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! trace_ioctl {
+    ($name:ident, $data:expr, $result:expr) => {};
+}
+
+/// Like [`nix::ioctl_none`], but issued through an [`IoctlBackend`].
+macro_rules! drm_ioctl_none {
+    ($(#[$attr:meta])* $name:ident, $ioty:expr, $nr:expr) => {
+        $(#[$attr])*
+        ///
+        /// # Safety
+        ///
+        /// The ioctl is passed to `backend` as is, see
+        /// [`IoctlBackend::ioctl`](crate::ioctl::IoctlBackend::ioctl).
+        pub unsafe fn $name<B: $crate::ioctl::IoctlBackend + ?Sized>(
+            backend: &B,
+        ) -> nix::Result<nix::libc::c_int> {
+            let result = backend.ioctl(
+                request_code_none!($ioty, $nr) as nix::sys::ioctl::ioctl_num_type,
+                ::std::ptr::null_mut(),
+            );
+            trace_ioctl!($name, (), result);
+            result
+        }
+    };
+}
+
+/// Like [`nix::ioctl_read`], but issued through an [`IoctlBackend`].
+macro_rules! drm_ioctl_read {
+    ($(#[$attr:meta])* $name:ident, $ioty:expr, $nr:expr, $ty:ty) => {
+        $(#[$attr])*
+        ///
+        /// # Safety
+        ///
+        /// `data` must point to a valid argument of the ioctl, including any
+        /// buffers it points to, see
+        /// [`IoctlBackend::ioctl`](crate::ioctl::IoctlBackend::ioctl).
+        pub unsafe fn $name<B: $crate::ioctl::IoctlBackend + ?Sized>(
+            backend: &B,
+            data: *mut $ty,
+        ) -> nix::Result<nix::libc::c_int> {
+            let result = backend.ioctl(
+                request_code_read!($ioty, $nr, ::std::mem::size_of::<$ty>())
+                    as nix::sys::ioctl::ioctl_num_type,
+                data as *mut nix::libc::c_void,
+            );
+            trace_ioctl!($name, *data, result);
+            result
+        }
+    };
+}
+
+/// Like [`nix::ioctl_write_ptr`], but issued through an [`IoctlBackend`].
+macro_rules! drm_ioctl_write_ptr {
+    ($(#[$attr:meta])* $name:ident, $ioty:expr, $nr:expr, $ty:ty) => {
+        $(#[$attr])*
+        ///
+        /// # Safety
+        ///
+        /// `data` must point to a valid argument of the ioctl, including any
+        /// buffers it points to, see
+        /// [`IoctlBackend::ioctl`](crate::ioctl::IoctlBackend::ioctl).
+        pub unsafe fn $name<B: $crate::ioctl::IoctlBackend + ?Sized>(
+            backend: &B,
+            data: *const $ty,
+        ) -> nix::Result<nix::libc::c_int> {
+            let result = backend.ioctl(
+                request_code_write!($ioty, $nr, ::std::mem::size_of::<$ty>())
+                    as nix::sys::ioctl::ioctl_num_type,
+                data as *mut nix::libc::c_void,
+            );
+            trace_ioctl!($name, *data, result);
+            result
+        }
+    };
+}
+
+/// Like [`nix::ioctl_readwrite`], but issued through an [`IoctlBackend`].
+macro_rules! drm_ioctl_readwrite {
+    ($(#[$attr:meta])* $name:ident, $ioty:expr, $nr:expr, $ty:ty) => {
+        $(#[$attr])*
+        ///
+        /// # Safety
+        ///
+        /// `data` must point to a valid argument of the ioctl, including any
+        /// buffers it points to, see
+        /// [`IoctlBackend::ioctl`](crate::ioctl::IoctlBackend::ioctl).
+        pub unsafe fn $name<B: $crate::ioctl::IoctlBackend + ?Sized>(
+            backend: &B,
+            data: *mut $ty,
+        ) -> nix::Result<nix::libc::c_int> {
+            let result = backend.ioctl(
+                request_code_readwrite!($ioty, $nr, ::std::mem::size_of::<$ty>())
+                    as nix::sys::ioctl::ioctl_num_type,
+                data as *mut nix::libc::c_void,
+            );
+            trace_ioctl!($name, *data, result);
+            result
+        }
+    };
+}
 
 /// Gets the bus ID of the device
 ///
 /// # Locks DRM mutex: Yes
 /// # Permissions: None
 /// # Nodes: Primary
-ioctl_readwrite!(get_bus_id, DRM_IOCTL_BASE, 0x01, drm_unique);
+drm_ioctl_readwrite!(get_bus_id, DRM_IOCTL_BASE, 0x01, drm_unique);
 
 /// Get information about the client
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: None
 /// # Nodes: Primary
-ioctl_readwrite!(get_client, DRM_IOCTL_BASE, 0x05, drm_client);
+drm_ioctl_readwrite!(get_client, DRM_IOCTL_BASE, 0x05, drm_client);
 
 /// Gets statistical information from the device
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: None
 /// # Nodes: Primary
-ioctl_read!(get_stats, DRM_IOCTL_BASE, 0x06, drm_stats);
+drm_ioctl_read!(get_stats, DRM_IOCTL_BASE, 0x06, drm_stats);
 
 /// Get capabilities of the device.
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: None
 /// # Nodes: Primary, Render
-ioctl_readwrite!(get_cap, DRM_IOCTL_BASE, 0x0c, drm_get_cap);
+drm_ioctl_readwrite!(get_cap, DRM_IOCTL_BASE, 0x0c, drm_get_cap);
 
 /// Tells the device we understand a capability
 ///
 /// # Locks DRM mutex: Yes
 /// # Permissions: None
 /// # Nodes: Primary
-ioctl_write_ptr!(set_cap, DRM_IOCTL_BASE, 0x0d, drm_set_client_cap);
+drm_ioctl_write_ptr!(set_cap, DRM_IOCTL_BASE, 0x0d, drm_set_client_cap);
 
 /// Sets the requested interface version
 ///
 /// # Locks DRM mutex: Yes
 /// # Permissions: Master
 /// # Nodes: Primary, control
-ioctl_readwrite!(set_version, DRM_IOCTL_BASE, 0x07, drm_set_version);
+drm_ioctl_readwrite!(set_version, DRM_IOCTL_BASE, 0x07, drm_set_version);
 
 /// Gets the current interface version
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: None
 /// # Nodes: All
-ioctl_readwrite!(get_version, DRM_IOCTL_BASE, 0x00, drm_version);
+drm_ioctl_readwrite!(get_version, DRM_IOCTL_BASE, 0x00, drm_version);
+
+/// Sets the name of the client shown in debugfs and fdinfo
+///
+/// # Locks DRM mutex: No
+/// # Permissions: None
+/// # Nodes: Primary, Render
+drm_ioctl_readwrite!(set_client_name, DRM_IOCTL_BASE, 0xD1, ::drm_set_client_name);
 
 /// Generates the client's authentication token
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: None
 /// # Nodes: Primary
-ioctl_read!(get_token, DRM_IOCTL_BASE, 0x02, drm_auth);
+drm_ioctl_read!(get_token, DRM_IOCTL_BASE, 0x02, drm_auth);
 
 /// Authenticates a client via their authentication token
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: Auth, Master
 /// # Nodes: Primary
-ioctl_write_ptr!(auth_token, DRM_IOCTL_BASE, 0x11, drm_auth);
+drm_ioctl_write_ptr!(auth_token, DRM_IOCTL_BASE, 0x11, drm_auth);
 
 /// Acquires the DRM Master lock
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: Root
 /// # Nodes: Primary
-ioctl_none!(acquire_master, DRM_IOCTL_BASE, 0x1e);
+drm_ioctl_none!(acquire_master, DRM_IOCTL_BASE, 0x1e);
 
 /// Drops the DRM Master lock
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: Root
 /// # Nodes: Primary
-ioctl_none!(release_master, DRM_IOCTL_BASE, 0x1f);
+drm_ioctl_none!(release_master, DRM_IOCTL_BASE, 0x1f);
 
 /// Gets the IRQ number
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: None
 /// # Nodes: Primary
-ioctl_readwrite!(get_irq_from_bus_id, DRM_IOCTL_BASE, 0x03, drm_irq_busid);
+drm_ioctl_readwrite!(get_irq_from_bus_id, DRM_IOCTL_BASE, 0x03, drm_irq_busid);
 
 /// Enable the vblank interrupt and sleep until the requested sequence occurs
 ///
 /// # Locks DRM mutex: No
 /// # Permissions: None
 /// # Nodes: Primary
-ioctl_readwrite!(wait_vblank, DRM_IOCTL_BASE, 0x3a, drm_wait_vblank);
+drm_ioctl_readwrite!(wait_vblank, DRM_IOCTL_BASE, 0x3a, drm_wait_vblank);
 
 pub(crate) mod mode {
     use drm_sys::*;
     use nix::libc::c_uint;
 
     /// Modesetting resources
-    ioctl_readwrite!(get_resources, DRM_IOCTL_BASE, 0xA0, drm_mode_card_res);
+    drm_ioctl_readwrite!(get_resources, DRM_IOCTL_BASE, 0xA0, drm_mode_card_res);
 
-    ioctl_readwrite!(
+    drm_ioctl_readwrite!(
         get_plane_resources,
         DRM_IOCTL_BASE,
         0xB5,
@@ -108,62 +277,66 @@ pub(crate) mod mode {
     );
 
     /// Connector related functions
-    ioctl_readwrite!(get_connector, DRM_IOCTL_BASE, 0xA7, drm_mode_get_connector);
+    drm_ioctl_readwrite!(get_connector, DRM_IOCTL_BASE, 0xA7, drm_mode_get_connector);
 
     /// Encoder related functions
-    ioctl_readwrite!(get_encoder, DRM_IOCTL_BASE, 0xA6, drm_mode_get_encoder);
+    drm_ioctl_readwrite!(get_encoder, DRM_IOCTL_BASE, 0xA6, drm_mode_get_encoder);
 
     /// CRTC related functions
-    ioctl_readwrite!(get_crtc, DRM_IOCTL_BASE, 0xA1, drm_mode_crtc);
-    ioctl_readwrite!(set_crtc, DRM_IOCTL_BASE, 0xA2, drm_mode_crtc);
+    drm_ioctl_readwrite!(get_crtc, DRM_IOCTL_BASE, 0xA1, drm_mode_crtc);
+    drm_ioctl_readwrite!(set_crtc, DRM_IOCTL_BASE, 0xA2, drm_mode_crtc);
 
     /// Gamma related functions
-    ioctl_readwrite!(get_gamma, DRM_IOCTL_BASE, 0xA4, drm_mode_crtc_lut);
-    ioctl_readwrite!(set_gamma, DRM_IOCTL_BASE, 0xA5, drm_mode_crtc_lut);
+    drm_ioctl_readwrite!(get_gamma, DRM_IOCTL_BASE, 0xA4, drm_mode_crtc_lut);
+    drm_ioctl_readwrite!(set_gamma, DRM_IOCTL_BASE, 0xA5, drm_mode_crtc_lut);
 
     // TODO: Figure out GAMMA LUT arrays
 
     /// FB related functions
-    ioctl_readwrite!(get_fb, DRM_IOCTL_BASE, 0xAD, drm_mode_fb_cmd);
-    ioctl_readwrite!(get_fb2, DRM_IOCTL_BASE, 0xCE, drm_mode_fb_cmd2);
-    ioctl_readwrite!(add_fb, DRM_IOCTL_BASE, 0xAE, drm_mode_fb_cmd);
-    ioctl_readwrite!(add_fb2, DRM_IOCTL_BASE, 0xB8, drm_mode_fb_cmd2);
-    ioctl_readwrite!(rm_fb, DRM_IOCTL_BASE, 0xAF, c_uint);
+    drm_ioctl_readwrite!(get_fb, DRM_IOCTL_BASE, 0xAD, drm_mode_fb_cmd);
+    drm_ioctl_readwrite!(get_fb2, DRM_IOCTL_BASE, 0xCE, drm_mode_fb_cmd2);
+    drm_ioctl_readwrite!(add_fb, DRM_IOCTL_BASE, 0xAE, drm_mode_fb_cmd);
+    drm_ioctl_readwrite!(add_fb2, DRM_IOCTL_BASE, 0xB8, drm_mode_fb_cmd2);
+    drm_ioctl_readwrite!(rm_fb, DRM_IOCTL_BASE, 0xAF, c_uint);
 
     /// Plane related functions
-    ioctl_readwrite!(get_plane, DRM_IOCTL_BASE, 0xB6, drm_mode_get_plane);
+    drm_ioctl_readwrite!(get_plane, DRM_IOCTL_BASE, 0xB6, drm_mode_get_plane);
 
-    ioctl_readwrite!(set_plane, DRM_IOCTL_BASE, 0xB7, drm_mode_set_plane);
+    drm_ioctl_readwrite!(set_plane, DRM_IOCTL_BASE, 0xB7, drm_mode_set_plane);
 
     /// Dumbbuffer related functions
-    ioctl_readwrite!(create_dumb, DRM_IOCTL_BASE, 0xB2, drm_mode_create_dumb);
+    drm_ioctl_readwrite!(create_dumb, DRM_IOCTL_BASE, 0xB2, drm_mode_create_dumb);
 
-    ioctl_readwrite!(map_dumb, DRM_IOCTL_BASE, 0xB3, drm_mode_map_dumb);
+    drm_ioctl_readwrite!(map_dumb, DRM_IOCTL_BASE, 0xB3, drm_mode_map_dumb);
 
-    ioctl_readwrite!(destroy_dumb, DRM_IOCTL_BASE, 0xB4, drm_mode_destroy_dumb);
+    drm_ioctl_readwrite!(destroy_dumb, DRM_IOCTL_BASE, 0xB4, drm_mode_destroy_dumb);
 
     /// Cursor related functions
-    ioctl_readwrite!(cursor, DRM_IOCTL_BASE, 0xA3, drm_mode_cursor);
-    ioctl_readwrite!(cursor2, DRM_IOCTL_BASE, 0xBB, drm_mode_cursor2);
+    drm_ioctl_readwrite!(cursor, DRM_IOCTL_BASE, 0xA3, drm_mode_cursor);
+    drm_ioctl_readwrite!(cursor2, DRM_IOCTL_BASE, 0xBB, drm_mode_cursor2);
+
+    /// User mode related functions
+    drm_ioctl_readwrite!(attach_mode, DRM_IOCTL_BASE, 0xA8, drm_mode_mode_cmd);
+    drm_ioctl_readwrite!(detach_mode, DRM_IOCTL_BASE, 0xA9, drm_mode_mode_cmd);
 
     /// Property related functions
-    ioctl_readwrite!(get_property, DRM_IOCTL_BASE, 0xAA, drm_mode_get_property);
+    drm_ioctl_readwrite!(get_property, DRM_IOCTL_BASE, 0xAA, drm_mode_get_property);
 
-    ioctl_readwrite!(
+    drm_ioctl_readwrite!(
         connector_set_property,
         DRM_IOCTL_BASE,
         0xAB,
         drm_mode_connector_set_property
     );
 
-    ioctl_readwrite!(
+    drm_ioctl_readwrite!(
         obj_get_properties,
         DRM_IOCTL_BASE,
         0xB9,
         drm_mode_obj_get_properties
     );
 
-    ioctl_readwrite!(
+    drm_ioctl_readwrite!(
         obj_set_property,
         DRM_IOCTL_BASE,
         0xBA,
@@ -171,37 +344,51 @@ pub(crate) mod mode {
     );
 
     /// Property blobs
-    ioctl_readwrite!(get_blob, DRM_IOCTL_BASE, 0xAC, drm_mode_get_blob);
+    drm_ioctl_readwrite!(get_blob, DRM_IOCTL_BASE, 0xAC, drm_mode_get_blob);
 
     // TODO: Property blobs probably require a large buffer
 
-    ioctl_readwrite!(create_blob, DRM_IOCTL_BASE, 0xBD, drm_mode_create_blob);
+    drm_ioctl_readwrite!(create_blob, DRM_IOCTL_BASE, 0xBD, drm_mode_create_blob);
 
-    ioctl_readwrite!(destroy_blob, DRM_IOCTL_BASE, 0xBE, drm_mode_destroy_blob);
+    drm_ioctl_readwrite!(destroy_blob, DRM_IOCTL_BASE, 0xBE, drm_mode_destroy_blob);
 
     /// Atomic modesetting related functions
-    ioctl_readwrite!(
+    drm_ioctl_readwrite!(
         crtc_page_flip,
         DRM_IOCTL_BASE,
         0xB0,
         drm_mode_crtc_page_flip
     );
 
-    ioctl_readwrite!(dirty_fb, DRM_IOCTL_BASE, 0xB1, drm_mode_fb_dirty_cmd);
+    drm_ioctl_readwrite!(dirty_fb, DRM_IOCTL_BASE, 0xB1, drm_mode_fb_dirty_cmd);
+
+    drm_ioctl_readwrite!(atomic, DRM_IOCTL_BASE, 0xBC, drm_mode_atomic);
+
+    /// Lease related functions
+    drm_ioctl_readwrite!(create_lease, DRM_IOCTL_BASE, 0xC6, drm_mode_create_lease);
 
-    ioctl_readwrite!(atomic, DRM_IOCTL_BASE, 0xBC, drm_mode_atomic);
+    drm_ioctl_readwrite!(list_lessees, DRM_IOCTL_BASE, 0xC7, drm_mode_list_lessees);
+
+    drm_ioctl_readwrite!(get_lease, DRM_IOCTL_BASE, 0xC8, drm_mode_get_lease);
+
+    drm_ioctl_readwrite!(revoke_lease, DRM_IOCTL_BASE, 0xC9, drm_mode_revoke_lease);
 }
 
 pub(crate) mod gem {
     use drm_sys::*;
 
     /// GEM related functions
-    ioctl_readwrite!(open, DRM_IOCTL_BASE, 0x0b, drm_gem_open);
-    ioctl_write_ptr!(close, DRM_IOCTL_BASE, 0x09, drm_gem_close);
+    drm_ioctl_readwrite!(open, DRM_IOCTL_BASE, 0x0b, drm_gem_open);
+    drm_ioctl_write_ptr!(close, DRM_IOCTL_BASE, 0x09, drm_gem_close);
 
     /// Converts a buffer handle into a dma-buf file descriptor.
-    ioctl_readwrite!(prime_handle_to_fd, DRM_IOCTL_BASE, 0x2d, drm_prime_handle);
+    drm_ioctl_readwrite!(prime_handle_to_fd, DRM_IOCTL_BASE, 0x2d, drm_prime_handle);
 
     /// Converts a dma-buf file descriptor into a buffer handle.
-    ioctl_readwrite!(prime_fd_to_handle, DRM_IOCTL_BASE, 0x2e, drm_prime_handle);
+    drm_ioctl_readwrite!(prime_fd_to_handle, DRM_IOCTL_BASE, 0x2e, drm_prime_handle);
+}
+
+pub(crate) mod dma_buf {
+    /// Brackets CPU access to a mapped dma-buf.
+    drm_ioctl_write_ptr!(sync, b'b', 0, ::dma_buf::dma_buf_sync);
 }
@@ -16,59 +16,59 @@ extern crate nix;
 pub(crate) mod utils;
 
 use result::SystemError as Error;
+pub mod dma_buf;
 pub mod gem;
 pub mod ioctl;
 pub mod mode;
 pub mod result;
 
+use ioctl::IoctlBackend;
 use nix::libc::*;
-use std::os::unix::io::RawFd;
 
 ///
 /// Bindings to the methods of authentication the DRM provides.
 ///
 pub mod auth {
     use drm_sys::*;
-    use ioctl;
+    use ioctl::{self, IoctlBackend};
 
     use nix::Error;
-    use std::os::unix::io::RawFd;
 
     /// Get the 'Magic Authentication Token' for this file descriptor.
-    pub fn get_magic_token(fd: RawFd) -> Result<drm_auth, Error> {
+    pub fn get_magic_token(fd: impl IoctlBackend) -> Result<drm_auth, Error> {
         let mut auth = drm_auth::default();
 
         unsafe {
-            ioctl::get_token(fd, &mut auth)?;
+            ioctl::get_token(&fd, &mut auth)?;
         }
 
         Ok(auth)
     }
 
     /// Authorize another process' 'Magic Authentication Token'.
-    pub fn auth_magic_token(fd: RawFd, auth: u32) -> Result<drm_auth, Error> {
+    pub fn auth_magic_token(fd: impl IoctlBackend, auth: u32) -> Result<drm_auth, Error> {
         let token = drm_auth { magic: auth };
 
         unsafe {
-            ioctl::auth_token(fd, &token)?;
+            ioctl::auth_token(&fd, &token)?;
         }
 
         Ok(token)
     }
 
     /// Acquire the 'Master DRM Lock' for this file descriptor.
-    pub fn acquire_master(fd: RawFd) -> Result<(), Error> {
+    pub fn acquire_master(fd: impl IoctlBackend) -> Result<(), Error> {
         unsafe {
-            ioctl::acquire_master(fd)?;
+            ioctl::acquire_master(&fd)?;
         }
 
         Ok(())
     }
 
     /// Release the 'Master DRM Lock' for this file descriptor.
-    pub fn release_master(fd: RawFd) -> Result<(), Error> {
+    pub fn release_master(fd: impl IoctlBackend) -> Result<(), Error> {
         unsafe {
-            ioctl::release_master(fd)?;
+            ioctl::release_master(&fd)?;
         }
 
         Ok(())
@@ -76,10 +76,13 @@ pub mod auth {
 }
 
 /// Load this device's Bus ID into a buffer.
-pub fn get_bus_id(fd: RawFd, mut buf: Option<&mut Vec<u8>>) -> Result<drm_unique, Error> {
+pub fn get_bus_id(
+    fd: impl IoctlBackend,
+    mut buf: Option<&mut Vec<u8>>,
+) -> Result<drm_unique, Error> {
     let mut sizes = drm_unique::default();
     unsafe {
-        ioctl::get_bus_id(fd, &mut sizes)?;
+        ioctl::get_bus_id(&fd, &mut sizes)?;
     }
 
     if buf.is_none() {
@@ -94,7 +97,7 @@ pub fn get_bus_id(fd: RawFd, mut buf: Option<&mut Vec<u8>>) -> Result<drm_unique
     };
 
     unsafe {
-        ioctl::get_bus_id(fd, &mut busid)?;
+        ioctl::get_bus_id(&fd, &mut busid)?;
     }
 
     map_set!(buf, busid.unique_len as usize);
@@ -104,7 +107,7 @@ pub fn get_bus_id(fd: RawFd, mut buf: Option<&mut Vec<u8>>) -> Result<drm_unique
 
 /// Get a device's IRQ.
 pub fn get_interrupt_from_bus_id(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     bus: c_int,
     dev: c_int,
     func: c_int,
@@ -117,64 +120,95 @@ pub fn get_interrupt_from_bus_id(
     };
 
     unsafe {
-        ioctl::get_irq_from_bus_id(fd, &mut irq)?;
+        ioctl::get_irq_from_bus_id(&fd, &mut irq)?;
     }
 
     Ok(irq)
 }
 
 /// Get client information given a client's ID.
-pub fn get_client(fd: RawFd, idx: c_int) -> Result<drm_client, Error> {
+pub fn get_client(fd: impl IoctlBackend, idx: c_int) -> Result<drm_client, Error> {
     let mut client = drm_client {
         idx,
         ..Default::default()
     };
 
     unsafe {
-        ioctl::get_client(fd, &mut client)?;
+        ioctl::get_client(&fd, &mut client)?;
     }
 
     Ok(client)
 }
 
 /// Check if a capability is set.
-pub fn get_capability(fd: RawFd, cty: u64) -> Result<drm_get_cap, Error> {
+pub fn get_capability(fd: impl IoctlBackend, cty: u64) -> Result<drm_get_cap, Error> {
     let mut cap = drm_get_cap {
         capability: cty,
         ..Default::default()
     };
 
     unsafe {
-        ioctl::get_cap(fd, &mut cap)?;
+        ioctl::get_cap(&fd, &mut cap)?;
     }
 
     Ok(cap)
 }
 
 /// Attempt to enable/disable a client's capability.
-pub fn set_capability(fd: RawFd, cty: u64, val: bool) -> Result<drm_set_client_cap, Error> {
+pub fn set_capability(
+    fd: impl IoctlBackend,
+    cty: u64,
+    val: bool,
+) -> Result<drm_set_client_cap, Error> {
     let cap = drm_set_client_cap {
         capability: cty,
         value: val as u64,
     };
 
     unsafe {
-        ioctl::set_cap(fd, &cap)?;
+        ioctl::set_cap(&fd, &cap)?;
     }
 
     Ok(cap)
 }
 
+/// Argument of `DRM_IOCTL_SET_CLIENT_NAME`, which is missing from the
+/// bundled bindings.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct drm_set_client_name {
+    /// Length of the name in bytes
+    pub name_len: u64,
+    /// Pointer to the name, which does not need to be null terminated
+    pub name: u64,
+}
+
+/// Sets the name of the client, or clears it if `name` is empty.
+pub fn set_client_name(fd: impl IoctlBackend, name: &[u8]) -> Result<(), Error> {
+    let mut client = drm_set_client_name {
+        name_len: name.len() as u64,
+        name: name.as_ptr() as u64,
+    };
+
+    // Kernels without the ioctl may report it as unknown
+    match unsafe { ioctl::set_client_name(&fd, &mut client) } {
+        Ok(_) => Ok(()),
+        Err(nix::errno::Errno::ENOTTY) => Err(Error::Unsupported),
+        Err(err) => Err(err.into()),
+    }
+}
+
 /// Gets the driver version for this device.
 pub fn get_version(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     mut name_buf: Option<&mut Vec<i8>>,
     mut date_buf: Option<&mut Vec<i8>>,
     mut desc_buf: Option<&mut Vec<i8>>,
 ) -> Result<drm_version, Error> {
     let mut sizes = drm_version::default();
     unsafe {
-        ioctl::get_version(fd, &mut sizes)?;
+        ioctl::get_version(&fd, &mut sizes)?;
     }
 
     map_reserve!(name_buf, sizes.name_len as usize);
@@ -192,7 +226,7 @@ pub fn get_version(
     };
 
     unsafe {
-        ioctl::get_version(fd, &mut version)?;
+        ioctl::get_version(&fd, &mut version)?;
     }
 
     map_set!(name_buf, version.name_len as usize);
@@ -204,7 +238,7 @@ pub fn get_version(
 
 /// Waits for a vblank.
 pub fn wait_vblank(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     type_: u32,
     sequence: u32,
     signal: usize,
@@ -221,7 +255,7 @@ pub fn wait_vblank(
     };
 
     unsafe {
-        ioctl::wait_vblank(fd, &mut wait_vblank)?;
+        ioctl::wait_vblank(&fd, &mut wait_vblank)?;
     };
 
     Ok(unsafe { wait_vblank.reply })
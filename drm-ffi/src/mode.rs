@@ -5,14 +5,13 @@
 #![allow(clippy::too_many_arguments)]
 
 use drm_sys::*;
-use ioctl;
+use ioctl::{self, IoctlBackend};
 
 use result::SystemError as Error;
-use std::os::unix::io::RawFd;
 
 /// Enumerate most card resources.
 pub fn get_resources(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     mut fbs: Option<&mut Vec<u32>>,
     mut crtcs: Option<&mut Vec<u32>>,
     mut connectors: Option<&mut Vec<u32>>,
@@ -20,7 +19,7 @@ pub fn get_resources(
 ) -> Result<drm_mode_card_res, Error> {
     let mut sizes = drm_mode_card_res::default();
     unsafe {
-        ioctl::mode::get_resources(fd, &mut sizes)?;
+        ioctl::mode::get_resources(&fd, &mut sizes)?;
     }
 
     map_reserve!(fbs, sizes.count_fbs as usize);
@@ -41,7 +40,7 @@ pub fn get_resources(
     };
 
     unsafe {
-        ioctl::mode::get_resources(fd, &mut res)?;
+        ioctl::mode::get_resources(&fd, &mut res)?;
     }
 
     map_set!(fbs, res.count_fbs as usize);
@@ -54,12 +53,12 @@ pub fn get_resources(
 
 /// Enumerate plane resources.
 pub fn get_plane_resources(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     mut planes: Option<&mut Vec<u32>>,
 ) -> Result<drm_mode_get_plane_res, Error> {
     let mut sizes = drm_mode_get_plane_res::default();
     unsafe {
-        ioctl::mode::get_plane_resources(fd, &mut sizes)?;
+        ioctl::mode::get_plane_resources(&fd, &mut sizes)?;
     }
 
     if planes.is_none() {
@@ -74,7 +73,7 @@ pub fn get_plane_resources(
     };
 
     unsafe {
-        ioctl::mode::get_plane_resources(fd, &mut res)?;
+        ioctl::mode::get_plane_resources(&fd, &mut res)?;
     }
 
     map_set!(planes, res.count_planes as usize);
@@ -83,14 +82,14 @@ pub fn get_plane_resources(
 }
 
 /// Get info about a framebuffer.
-pub fn get_framebuffer(fd: RawFd, fb_id: u32) -> Result<drm_mode_fb_cmd, Error> {
+pub fn get_framebuffer(fd: impl IoctlBackend, fb_id: u32) -> Result<drm_mode_fb_cmd, Error> {
     let mut info = drm_mode_fb_cmd {
         fb_id,
         ..Default::default()
     };
 
     unsafe {
-        ioctl::mode::get_fb(fd, &mut info)?;
+        ioctl::mode::get_fb(&fd, &mut info)?;
     }
 
     Ok(info)
@@ -98,7 +97,7 @@ pub fn get_framebuffer(fd: RawFd, fb_id: u32) -> Result<drm_mode_fb_cmd, Error>
 
 /// Add a new framebuffer.
 pub fn add_fb(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     width: u32,
     height: u32,
     pitch: u32,
@@ -117,21 +116,21 @@ pub fn add_fb(
     };
 
     unsafe {
-        ioctl::mode::add_fb(fd, &mut fb)?;
+        ioctl::mode::add_fb(&fd, &mut fb)?;
     }
 
     Ok(fb)
 }
 
 /// Get info about a framebuffer (with modifiers).
-pub fn get_framebuffer2(fd: RawFd, fb_id: u32) -> Result<drm_mode_fb_cmd2, Error> {
+pub fn get_framebuffer2(fd: impl IoctlBackend, fb_id: u32) -> Result<drm_mode_fb_cmd2, Error> {
     let mut info = drm_mode_fb_cmd2 {
         fb_id,
         ..Default::default()
     };
 
     unsafe {
-        ioctl::mode::get_fb2(fd, &mut info)?;
+        ioctl::mode::get_fb2(&fd, &mut info)?;
     }
 
     Ok(info)
@@ -139,7 +138,7 @@ pub fn get_framebuffer2(fd: RawFd, fb_id: u32) -> Result<drm_mode_fb_cmd2, Error
 
 /// Add a new framebuffer (with modifiers)
 pub fn add_fb2(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     width: u32,
     height: u32,
     fmt: u32,
@@ -162,50 +161,56 @@ pub fn add_fb2(
     };
 
     unsafe {
-        ioctl::mode::add_fb2(fd, &mut fb)?;
+        ioctl::mode::add_fb2(&fd, &mut fb)?;
     }
 
     Ok(fb)
 }
 
 /// Remove a framebuffer.
-pub fn rm_fb(fd: RawFd, mut id: u32) -> Result<(), Error> {
+pub fn rm_fb(fd: impl IoctlBackend, mut id: u32) -> Result<(), Error> {
     unsafe {
-        ioctl::mode::rm_fb(fd, &mut id)?;
+        ioctl::mode::rm_fb(&fd, &mut id)?;
     }
 
     Ok(())
 }
 
 /// Mark a framebuffer as dirty.
+///
+/// `flags` may contain one of the `DRM_MODE_FB_DIRTY_ANNOTATE_*` flags. `color`
+/// is only used with `DRM_MODE_FB_DIRTY_ANNOTATE_FILL`.
 pub fn dirty_fb(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     fb_id: u32,
+    flags: u32,
+    color: u32,
     clips: &[drm_clip_rect],
 ) -> Result<drm_mode_fb_dirty_cmd, Error> {
     let mut dirty = drm_mode_fb_dirty_cmd {
         fb_id,
+        flags,
+        color,
         num_clips: clips.len() as _,
         clips_ptr: clips.as_ptr() as _,
-        ..Default::default()
     };
 
     unsafe {
-        ioctl::mode::dirty_fb(fd, &mut dirty)?;
+        ioctl::mode::dirty_fb(&fd, &mut dirty)?;
     }
 
     Ok(dirty)
 }
 
 /// Get info about a CRTC
-pub fn get_crtc(fd: RawFd, crtc_id: u32) -> Result<drm_mode_crtc, Error> {
+pub fn get_crtc(fd: impl IoctlBackend, crtc_id: u32) -> Result<drm_mode_crtc, Error> {
     let mut info = drm_mode_crtc {
         crtc_id,
         ..Default::default()
     };
 
     unsafe {
-        ioctl::mode::get_crtc(fd, &mut info)?;
+        ioctl::mode::get_crtc(&fd, &mut info)?;
     }
 
     Ok(info)
@@ -213,7 +218,7 @@ pub fn get_crtc(fd: RawFd, crtc_id: u32) -> Result<drm_mode_crtc, Error> {
 
 /// Set CRTC state
 pub fn set_crtc(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     crtc_id: u32,
     fb_id: u32,
     x: u32,
@@ -237,7 +242,7 @@ pub fn set_crtc(
     };
 
     unsafe {
-        ioctl::mode::set_crtc(fd, &mut crtc)?;
+        ioctl::mode::set_crtc(&fd, &mut crtc)?;
     }
 
     Ok(crtc)
@@ -245,7 +250,7 @@ pub fn set_crtc(
 
 /// Get CRTC gamma ramp
 pub fn get_gamma(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     crtc_id: u32,
     size: usize,
     red: &mut [u16],
@@ -261,7 +266,7 @@ pub fn get_gamma(
     };
 
     unsafe {
-        ioctl::mode::get_gamma(fd, &mut lut)?;
+        ioctl::mode::get_gamma(&fd, &mut lut)?;
     }
 
     Ok(lut)
@@ -269,7 +274,7 @@ pub fn get_gamma(
 
 /// Set CRTC gamma ramp
 pub fn set_gamma(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     crtc_id: u32,
     size: usize,
     red: &[u16],
@@ -285,7 +290,7 @@ pub fn set_gamma(
     };
 
     unsafe {
-        ioctl::mode::set_gamma(fd, &mut lut)?;
+        ioctl::mode::set_gamma(&fd, &mut lut)?;
     }
 
     Ok(lut)
@@ -297,7 +302,7 @@ pub fn set_gamma(
 /// allowed to be a dumb buffer.
 #[deprecated = "use a cursor plane instead"]
 pub fn set_cursor(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     crtc_id: u32,
     buf_id: u32,
     width: u32,
@@ -313,7 +318,7 @@ pub fn set_cursor(
     };
 
     unsafe {
-        ioctl::mode::cursor(fd, &mut cursor)?;
+        ioctl::mode::cursor(&fd, &mut cursor)?;
     }
 
     Ok(cursor)
@@ -328,7 +333,7 @@ pub fn set_cursor(
 /// virtualization.
 #[deprecated = "use a cursor plane instead"]
 pub fn set_cursor2(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     crtc_id: u32,
     buf_id: u32,
     width: u32,
@@ -348,7 +353,7 @@ pub fn set_cursor2(
     };
 
     unsafe {
-        ioctl::mode::cursor2(fd, &mut cursor)?;
+        ioctl::mode::cursor2(&fd, &mut cursor)?;
     }
 
     Ok(cursor)
@@ -356,7 +361,12 @@ pub fn set_cursor2(
 
 /// Move cursor
 #[deprecated = "use a cursor plane instead"]
-pub fn move_cursor(fd: RawFd, crtc_id: u32, x: i32, y: i32) -> Result<drm_mode_cursor, Error> {
+pub fn move_cursor(
+    fd: impl IoctlBackend,
+    crtc_id: u32,
+    x: i32,
+    y: i32,
+) -> Result<drm_mode_cursor, Error> {
     let mut cursor = drm_mode_cursor {
         flags: DRM_MODE_CURSOR_MOVE,
         crtc_id,
@@ -366,15 +376,58 @@ pub fn move_cursor(fd: RawFd, crtc_id: u32, x: i32, y: i32) -> Result<drm_mode_c
     };
 
     unsafe {
-        ioctl::mode::cursor(fd, &mut cursor)?;
+        ioctl::mode::cursor(&fd, &mut cursor)?;
     }
 
     Ok(cursor)
 }
 
+/// Adds a user mode to a connector
+#[deprecated = "set a user defined mode through MODE_ID instead"]
+pub fn attach_mode(
+    fd: impl IoctlBackend,
+    connector_id: u32,
+    mode: drm_mode_modeinfo,
+) -> Result<drm_mode_mode_cmd, Error> {
+    let mut cmd = drm_mode_mode_cmd { connector_id, mode };
+
+    unsafe {
+        ioctl::mode::attach_mode(&fd, &mut cmd)?;
+    }
+
+    Ok(cmd)
+}
+
+/// Removes a user mode from a connector
+#[deprecated = "set a user defined mode through MODE_ID instead"]
+pub fn detach_mode(
+    fd: impl IoctlBackend,
+    connector_id: u32,
+    mode: drm_mode_modeinfo,
+) -> Result<drm_mode_mode_cmd, Error> {
+    let mut cmd = drm_mode_mode_cmd { connector_id, mode };
+
+    unsafe {
+        ioctl::mode::detach_mode(&fd, &mut cmd)?;
+    }
+
+    Ok(cmd)
+}
+
+/// Number of times [`get_connector`] re-queries a connector whose counts keep
+/// changing before giving up.
+pub const GET_CONNECTOR_MAX_RETRIES: usize = 4;
+
 /// Get info about a connector
+///
+/// If the connector keeps changing while its information is read, e.g. due to
+/// rapid hotplugging, this gives up after a few attempts and returns an
+/// `EAGAIN` error.
+///
+/// `props` and `prop_values` must either both be given or both be `None`,
+/// otherwise [`Error::InvalidArgument`] is returned.
 pub fn get_connector(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     connector_id: u32,
     mut props: Option<&mut Vec<u32>>,
     mut prop_values: Option<&mut Vec<u64>>,
@@ -382,7 +435,9 @@ pub fn get_connector(
     mut encoders: Option<&mut Vec<u32>>,
     force_probe: bool,
 ) -> Result<drm_mode_get_connector, Error> {
-    assert_eq!(props.is_some(), prop_values.is_some());
+    if props.is_some() != prop_values.is_some() {
+        return Err(Error::InvalidArgument);
+    }
 
     let tmp_mode = drm_mode_modeinfo::default();
     let mut sizes = drm_mode_get_connector {
@@ -397,9 +452,10 @@ pub fn get_connector(
     };
 
     unsafe {
-        ioctl::mode::get_connector(fd, &mut sizes)?;
+        ioctl::mode::get_connector(&fd, &mut sizes)?;
     }
 
+    let mut retries = 0;
     let info = loop {
         map_reserve!(props, sizes.count_props as usize);
         map_reserve!(prop_values, sizes.count_props as usize);
@@ -431,23 +487,34 @@ pub fn get_connector(
                     }
                 }
             },
-            count_props: map_len!(&props),
+            count_props: std::cmp::min(map_len!(&props), map_len!(&prop_values)),
             count_encoders: map_len!(&encoders),
             ..Default::default()
         };
 
+        let (count_modes, count_props, count_encoders) =
+            (info.count_modes, info.count_props, info.count_encoders);
+
         unsafe {
-            ioctl::mode::get_connector(fd, &mut info)?;
+            ioctl::mode::get_connector(&fd, &mut info)?;
         }
 
-        if info.count_modes == sizes.count_modes
-            && info.count_encoders == sizes.count_encoders
-            && info.count_props == sizes.count_props
+        // The kernel only fills a buffer if it is large enough to hold all
+        // entries, so retry with the new counts if the connector changed
+        // between the calls.
+        if (modes.is_none() || info.count_modes <= count_modes)
+            && (props.is_none() || info.count_props <= count_props)
+            && (encoders.is_none() || info.count_encoders <= count_encoders)
         {
             break info;
-        } else {
-            sizes = info;
         }
+
+        retries += 1;
+        if retries >= GET_CONNECTOR_MAX_RETRIES {
+            return Err(nix::errno::Errno::EAGAIN.into());
+        }
+
+        sizes = info;
     };
 
     map_set!(modes, info.count_modes as usize);
@@ -459,14 +526,14 @@ pub fn get_connector(
 }
 
 /// Get info about an encoder
-pub fn get_encoder(fd: RawFd, encoder_id: u32) -> Result<drm_mode_get_encoder, Error> {
+pub fn get_encoder(fd: impl IoctlBackend, encoder_id: u32) -> Result<drm_mode_get_encoder, Error> {
     let mut info = drm_mode_get_encoder {
         encoder_id,
         ..Default::default()
     };
 
     unsafe {
-        ioctl::mode::get_encoder(fd, &mut info)?;
+        ioctl::mode::get_encoder(&fd, &mut info)?;
     }
 
     Ok(info)
@@ -474,7 +541,7 @@ pub fn get_encoder(fd: RawFd, encoder_id: u32) -> Result<drm_mode_get_encoder, E
 
 /// Get info about a plane.
 pub fn get_plane(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     plane_id: u32,
     mut formats: Option<&mut Vec<u32>>,
 ) -> Result<drm_mode_get_plane, Error> {
@@ -484,7 +551,7 @@ pub fn get_plane(
     };
 
     unsafe {
-        ioctl::mode::get_plane(fd, &mut sizes)?;
+        ioctl::mode::get_plane(&fd, &mut sizes)?;
     }
 
     if formats.is_none() {
@@ -501,7 +568,7 @@ pub fn get_plane(
     };
 
     unsafe {
-        ioctl::mode::get_plane(fd, &mut info)?;
+        ioctl::mode::get_plane(&fd, &mut info)?;
     }
 
     map_set!(formats, info.count_format_types as usize);
@@ -511,7 +578,7 @@ pub fn get_plane(
 
 /// Set plane state.
 pub fn set_plane(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     plane_id: u32,
     crtc_id: u32,
     fb_id: u32,
@@ -541,7 +608,7 @@ pub fn set_plane(
     };
 
     unsafe {
-        ioctl::mode::set_plane(fd, &mut plane)?;
+        ioctl::mode::set_plane(&fd, &mut plane)?;
     }
 
     Ok(plane)
@@ -549,7 +616,7 @@ pub fn set_plane(
 
 /// Get property
 pub fn get_property(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     prop_id: u32,
     mut values: Option<&mut Vec<u64>>,
     mut enums: Option<&mut Vec<drm_mode_property_enum>>,
@@ -560,7 +627,7 @@ pub fn get_property(
     };
 
     unsafe {
-        ioctl::mode::get_property(fd, &mut sizes)?;
+        ioctl::mode::get_property(&fd, &mut sizes)?;
     }
 
     map_reserve!(values, sizes.count_values as usize);
@@ -576,7 +643,7 @@ pub fn get_property(
     };
 
     unsafe {
-        ioctl::mode::get_property(fd, &mut prop)?;
+        ioctl::mode::get_property(&fd, &mut prop)?;
     }
 
     map_set!(values, prop.count_values as usize);
@@ -587,7 +654,7 @@ pub fn get_property(
 
 /// Set property
 pub fn set_connector_property(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     connector_id: u32,
     prop_id: u32,
     value: u64,
@@ -599,7 +666,7 @@ pub fn set_connector_property(
     };
 
     unsafe {
-        ioctl::mode::connector_set_property(fd, &mut prop)?;
+        ioctl::mode::connector_set_property(&fd, &mut prop)?;
     }
 
     Ok(prop)
@@ -607,7 +674,7 @@ pub fn set_connector_property(
 
 /// Get the value of a property blob
 pub fn get_property_blob(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     blob_id: u32,
     mut data: Option<&mut Vec<u8>>,
 ) -> Result<drm_mode_get_blob, Error> {
@@ -617,7 +684,7 @@ pub fn get_property_blob(
     };
 
     unsafe {
-        ioctl::mode::get_blob(fd, &mut sizes)?;
+        ioctl::mode::get_blob(&fd, &mut sizes)?;
     }
 
     if data.is_none() {
@@ -633,7 +700,7 @@ pub fn get_property_blob(
     };
 
     unsafe {
-        ioctl::mode::get_blob(fd, &mut blob)?;
+        ioctl::mode::get_blob(&fd, &mut blob)?;
     }
 
     map_set!(data, blob.length as usize);
@@ -642,7 +709,10 @@ pub fn get_property_blob(
 }
 
 /// Create a property blob
-pub fn create_property_blob(fd: RawFd, data: &mut [u8]) -> Result<drm_mode_create_blob, Error> {
+pub fn create_property_blob(
+    fd: impl IoctlBackend,
+    data: &mut [u8],
+) -> Result<drm_mode_create_blob, Error> {
     let mut blob = drm_mode_create_blob {
         data: data.as_ptr() as _,
         length: data.len() as _,
@@ -650,32 +720,41 @@ pub fn create_property_blob(fd: RawFd, data: &mut [u8]) -> Result<drm_mode_creat
     };
 
     unsafe {
-        ioctl::mode::create_blob(fd, &mut blob)?;
+        ioctl::mode::create_blob(&fd, &mut blob)?;
     }
 
     Ok(blob)
 }
 
 /// Destroy a property blob
-pub fn destroy_property_blob(fd: RawFd, id: u32) -> Result<drm_mode_destroy_blob, Error> {
+pub fn destroy_property_blob(
+    fd: impl IoctlBackend,
+    id: u32,
+) -> Result<drm_mode_destroy_blob, Error> {
     let mut blob = drm_mode_destroy_blob { blob_id: id };
 
     unsafe {
-        ioctl::mode::destroy_blob(fd, &mut blob)?;
+        ioctl::mode::destroy_blob(&fd, &mut blob)?;
     }
 
     Ok(blob)
 }
 
 /// Get properties from an object
+///
+/// `obj_type` can be any `DRM_MODE_OBJECT_*` type, or `DRM_MODE_OBJECT_ANY`.
+/// `props` and `values` must either both be given or both be `None`,
+/// otherwise [`Error::InvalidArgument`] is returned.
 pub fn get_properties(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     obj_id: u32,
     obj_type: u32,
     mut props: Option<&mut Vec<u32>>,
     mut values: Option<&mut Vec<u64>>,
 ) -> Result<drm_mode_obj_get_properties, Error> {
-    assert_eq!(props.is_some(), values.is_some());
+    if props.is_some() != values.is_some() {
+        return Err(Error::InvalidArgument);
+    }
 
     let mut sizes = drm_mode_obj_get_properties {
         obj_id,
@@ -684,7 +763,7 @@ pub fn get_properties(
     };
 
     unsafe {
-        ioctl::mode::obj_get_properties(fd, &mut sizes)?;
+        ioctl::mode::obj_get_properties(&fd, &mut sizes)?;
     }
 
     map_reserve!(props, sizes.count_props as usize);
@@ -693,13 +772,13 @@ pub fn get_properties(
     let mut info = drm_mode_obj_get_properties {
         props_ptr: map_ptr!(&props),
         prop_values_ptr: map_ptr!(&values),
-        count_props: map_len!(&props),
+        count_props: std::cmp::min(map_len!(&props), map_len!(&values)),
         obj_id,
         obj_type,
     };
 
     unsafe {
-        ioctl::mode::obj_get_properties(fd, &mut info)?;
+        ioctl::mode::obj_get_properties(&fd, &mut info)?;
     }
 
     map_set!(props, info.count_props as usize);
@@ -710,7 +789,7 @@ pub fn get_properties(
 
 /// Set the properties of an object
 pub fn set_property(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     prop_id: u32,
     obj_id: u32,
     obj_type: u32,
@@ -724,7 +803,7 @@ pub fn set_property(
     };
 
     unsafe {
-        ioctl::mode::obj_set_property(fd, &mut prop)?;
+        ioctl::mode::obj_set_property(&fd, &mut prop)?;
     }
 
     Ok(())
@@ -732,7 +811,7 @@ pub fn set_property(
 
 /// Schedule a page flip
 pub fn page_flip(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     crtc_id: u32,
     fb_id: u32,
     flags: u32,
@@ -748,7 +827,7 @@ pub fn page_flip(
     };
 
     unsafe {
-        ioctl::mode::crtc_page_flip(fd, &mut flip)?;
+        ioctl::mode::crtc_page_flip(&fd, &mut flip)?;
     }
 
     Ok(())
@@ -756,7 +835,7 @@ pub fn page_flip(
 
 /// Atomically set properties
 pub fn atomic_commit(
-    fd: RawFd,
+    fd: impl IoctlBackend,
     flags: u32,
     objs: &mut [u32],
     prop_counts: &mut [u32],
@@ -774,7 +853,110 @@ pub fn atomic_commit(
     };
 
     unsafe {
-        ioctl::mode::atomic(fd, &mut atomic)?;
+        ioctl::mode::atomic(&fd, &mut atomic)?;
+    }
+
+    Ok(())
+}
+
+/// Create a lease
+pub fn create_lease(
+    fd: impl IoctlBackend,
+    objects: &[u32],
+    flags: u32,
+) -> Result<drm_mode_create_lease, Error> {
+    let mut data = drm_mode_create_lease {
+        object_ids: objects.as_ptr() as _,
+        object_count: objects.len() as u32,
+        flags,
+        ..Default::default()
+    };
+
+    unsafe {
+        ioctl::mode::create_lease(&fd, &mut data)?;
+    }
+
+    Ok(data)
+}
+
+/// List all lessees of this master
+pub fn list_lessees(
+    fd: impl IoctlBackend,
+    mut lessees: Option<&mut Vec<u32>>,
+) -> Result<drm_mode_list_lessees, Error> {
+    let mut sizes = drm_mode_list_lessees::default();
+    unsafe {
+        ioctl::mode::list_lessees(&fd, &mut sizes)?;
+    }
+
+    if lessees.is_none() {
+        return Ok(sizes);
+    }
+
+    map_reserve!(lessees, sizes.count_lessees as usize);
+
+    let mut data = drm_mode_list_lessees {
+        lessees_ptr: map_ptr!(&lessees),
+        count_lessees: map_len!(&lessees),
+        ..Default::default()
+    };
+
+    let capacity = data.count_lessees;
+    unsafe {
+        ioctl::mode::list_lessees(&fd, &mut data)?;
+    }
+
+    // The kernel only copies as many entries as fit, but reports all of them
+    map_set!(
+        lessees,
+        std::cmp::min(data.count_lessees, capacity) as usize
+    );
+
+    Ok(data)
+}
+
+/// Get leased objects of this lessee
+pub fn get_lease(
+    fd: impl IoctlBackend,
+    mut objects: Option<&mut Vec<u32>>,
+) -> Result<drm_mode_get_lease, Error> {
+    let mut sizes = drm_mode_get_lease::default();
+    unsafe {
+        ioctl::mode::get_lease(&fd, &mut sizes)?;
+    }
+
+    if objects.is_none() {
+        return Ok(sizes);
+    }
+
+    map_reserve!(objects, sizes.count_objects as usize);
+
+    let mut data = drm_mode_get_lease {
+        objects_ptr: map_ptr!(&objects),
+        count_objects: map_len!(&objects),
+        ..Default::default()
+    };
+
+    let capacity = data.count_objects;
+    unsafe {
+        ioctl::mode::get_lease(&fd, &mut data)?;
+    }
+
+    // The kernel only copies as many entries as fit, but reports all of them
+    map_set!(
+        objects,
+        std::cmp::min(data.count_objects, capacity) as usize
+    );
+
+    Ok(data)
+}
+
+/// Revoke a previously issued lease
+pub fn revoke_lease(fd: impl IoctlBackend, lessee_id: u32) -> Result<(), Error> {
+    let mut data = drm_mode_revoke_lease { lessee_id };
+
+    unsafe {
+        ioctl::mode::revoke_lease(&fd, &mut data)?;
     }
 
     Ok(())
@@ -785,14 +967,13 @@ pub fn atomic_commit(
 ///
 pub mod dumbbuffer {
     use drm_sys::*;
-    use ioctl;
+    use ioctl::{self, IoctlBackend};
 
     use result::SystemError as Error;
-    use std::os::unix::io::RawFd;
 
     /// Create a dumb buffer
     pub fn create(
-        fd: RawFd,
+        fd: impl IoctlBackend,
         width: u32,
         height: u32,
         bpp: u32,
@@ -807,25 +988,30 @@ pub mod dumbbuffer {
         };
 
         unsafe {
-            ioctl::mode::create_dumb(fd, &mut db)?;
+            ioctl::mode::create_dumb(&fd, &mut db)?;
         }
 
         Ok(db)
     }
 
     /// Destroy a dumb buffer
-    pub fn destroy(fd: RawFd, handle: u32) -> Result<drm_mode_destroy_dumb, Error> {
+    pub fn destroy(fd: impl IoctlBackend, handle: u32) -> Result<drm_mode_destroy_dumb, Error> {
         let mut db = drm_mode_destroy_dumb { handle };
 
         unsafe {
-            ioctl::mode::destroy_dumb(fd, &mut db)?;
+            ioctl::mode::destroy_dumb(&fd, &mut db)?;
         }
 
         Ok(db)
     }
 
     /// Map a dump buffer and prep it for an mmap
-    pub fn map(fd: RawFd, handle: u32, pad: u32, offset: u64) -> Result<drm_mode_map_dumb, Error> {
+    pub fn map(
+        fd: impl IoctlBackend,
+        handle: u32,
+        pad: u32,
+        offset: u64,
+    ) -> Result<drm_mode_map_dumb, Error> {
         let mut map = drm_mode_map_dumb {
             handle,
             pad,
@@ -833,7 +1019,7 @@ pub mod dumbbuffer {
         };
 
         unsafe {
-            ioctl::mode::map_dumb(fd, &mut map)?;
+            ioctl::mode::map_dumb(&fd, &mut map)?;
         }
 
         Ok(map)
@@ -36,6 +36,23 @@ pub enum SystemError {
     /// This likely indicates that the drm-fourcc crate needs updating.
     UnknownFourcc,
 
+    /// The operation is not supported by the device or its driver.
+    Unsupported,
+
+    /// An atomic commit changes the display mode, but was not allowed to.
+    ///
+    /// Returned instead of [`SystemError::InvalidArgument`] if the kernel
+    /// rejects a commit lacking the `DRM_MODE_ATOMIC_ALLOW_MODESET` flag that
+    /// needs it.
+    ModesetRequired,
+
+    /// An asynchronous atomic commit changes more than plane framebuffers.
+    ///
+    /// Returned instead of [`SystemError::InvalidArgument`] if the kernel
+    /// rejects a commit with the `DRM_MODE_PAGE_FLIP_ASYNC` flag, which only
+    /// allows changing the `FB_ID` of planes.
+    AsyncFlipNotAllowed,
+
     /// Unknown system error.
     Unknown {
         /// Unknown [`nix::errno::Errno`] returned by the system call.
@@ -52,6 +69,13 @@ impl fmt::Display for SystemError {
             SystemError::InvalidFileType => "invalid file type",
             SystemError::PermissionDenied => "permission denied",
             SystemError::UnknownFourcc => "unknown fourcc",
+            SystemError::Unsupported => "operation not supported by the device",
+            SystemError::ModesetRequired => {
+                "atomic commit requires a modeset, but it is not allowed"
+            }
+            SystemError::AsyncFlipNotAllowed => {
+                "asynchronous atomic commit may only change plane framebuffers"
+            }
             SystemError::Unknown { errno } => {
                 return write!(fmt, "unknown system error: {}", errno)
             }
@@ -69,6 +93,7 @@ impl From<Errno> for SystemError {
             Errno::EINVAL => SystemError::InvalidArgument,
             Errno::ENOTTY => SystemError::InvalidFileDescriptor,
             Errno::EACCES => SystemError::PermissionDenied,
+            Errno::EOPNOTSUPP => SystemError::Unsupported,
             _ => SystemError::Unknown { errno },
         }
     }
@@ -22,7 +22,7 @@ macro_rules! map_len {
 macro_rules! map_reserve {
     ($buffer:expr, $size:expr) => {
         match $buffer {
-            Some(ref mut b) => b.reserve_exact($size - b.len()),
+            Some(ref mut b) => b.reserve_exact($size.saturating_sub(b.len())),
             _ => (),
         }
     };
@@ -31,7 +31,7 @@ macro_rules! map_reserve {
 macro_rules! map_set {
     ($buffer:expr, $min:expr) => {
         match $buffer {
-            Some(ref mut b) => unsafe { b.set_len($min) },
+            Some(ref mut b) => unsafe { b.set_len(::std::cmp::min($min, b.capacity())) },
             _ => (),
         }
     };
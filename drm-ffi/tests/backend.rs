@@ -0,0 +1,146 @@
+//! Drives the ioctl wrappers through fake [`IoctlBackend`]s.
+
+extern crate drm_ffi;
+extern crate nix;
+
+use drm_ffi::ioctl::{command_number, IoctlBackend};
+use drm_ffi::*;
+use nix::libc::{c_int, c_void};
+use nix::sys::ioctl::ioctl_num_type;
+use std::cell::Cell;
+
+/// Command number of `DRM_IOCTL_MODE_GETRESOURCES`
+const GET_RESOURCES: u8 = 0xA0;
+/// Command number of `DRM_IOCTL_MODE_GETCONNECTOR`
+const GET_CONNECTOR: u8 = 0xA7;
+
+/// Fakes a device with the given CRTCs and connectors, and no framebuffers or
+/// encoders.
+struct Resources {
+    crtcs: Vec<u32>,
+    connectors: Vec<u32>,
+}
+
+/// Copies `ids` to `ptr` if `count` is large enough, like the kernel does,
+/// and reports the number of ids.
+unsafe fn fill(ptr: u64, count: &mut u32, ids: &[u32]) {
+    if ptr != 0 && *count as usize >= ids.len() {
+        std::ptr::copy_nonoverlapping(ids.as_ptr(), ptr as *mut u32, ids.len());
+    }
+    *count = ids.len() as u32;
+}
+
+unsafe impl IoctlBackend for Resources {
+    unsafe fn ioctl(&self, request: ioctl_num_type, arg: *mut c_void) -> nix::Result<c_int> {
+        if command_number(request) != GET_RESOURCES {
+            return Err(nix::errno::Errno::ENOTTY);
+        }
+
+        let res = &mut *(arg as *mut drm_mode_card_res);
+        fill(res.crtc_id_ptr, &mut res.count_crtcs, &self.crtcs);
+        fill(
+            res.connector_id_ptr,
+            &mut res.count_connectors,
+            &self.connectors,
+        );
+        fill(res.fb_id_ptr, &mut res.count_fbs, &[]);
+        fill(res.encoder_id_ptr, &mut res.count_encoders, &[]);
+        Ok(0)
+    }
+}
+
+#[test]
+fn get_resources_reads_ids() {
+    let backend = Resources {
+        crtcs: vec![31, 32],
+        connectors: vec![40, 41, 42],
+    };
+
+    let mut crtcs = Vec::new();
+    let mut connectors = Vec::new();
+    let res = mode::get_resources(
+        &backend,
+        None,
+        Some(&mut crtcs),
+        Some(&mut connectors),
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(res.count_crtcs, 2);
+    assert_eq!(crtcs, [31, 32]);
+    assert_eq!(connectors, [40, 41, 42]);
+}
+
+#[test]
+fn unknown_ioctls_fail() {
+    let backend = Resources {
+        crtcs: Vec::new(),
+        connectors: Vec::new(),
+    };
+
+    assert!(mode::get_plane_resources(&backend, None).is_err());
+}
+
+/// Fakes a connector whose number of encoders changes between calls, e.g.
+/// while it is hotplugged, before settling on the last count.
+struct Hotplug {
+    encoder_counts: Vec<u32>,
+    calls: Cell<usize>,
+}
+
+impl Hotplug {
+    fn new(encoder_counts: Vec<u32>) -> Hotplug {
+        Hotplug {
+            encoder_counts,
+            calls: Cell::new(0),
+        }
+    }
+}
+
+unsafe impl IoctlBackend for Hotplug {
+    unsafe fn ioctl(&self, request: ioctl_num_type, arg: *mut c_void) -> nix::Result<c_int> {
+        if command_number(request) != GET_CONNECTOR {
+            return Err(nix::errno::Errno::ENOTTY);
+        }
+
+        let call = self.calls.get();
+        self.calls.set(call + 1);
+        let count = self.encoder_counts[call.min(self.encoder_counts.len() - 1)];
+        let encoders: Vec<u32> = (1..=count).collect();
+
+        let info = &mut *(arg as *mut drm_mode_get_connector);
+        fill(info.encoders_ptr, &mut info.count_encoders, &encoders);
+        info.count_modes = 0;
+        info.count_props = 0;
+        Ok(0)
+    }
+}
+
+#[test]
+fn get_connector_retries_until_counts_settle() {
+    let backend = Hotplug::new(vec![1, 2, 3, 3]);
+
+    let mut encoders = Vec::new();
+    mode::get_connector(&backend, 1, None, None, None, Some(&mut encoders), false).unwrap();
+
+    assert_eq!(encoders, [1, 2, 3]);
+    assert_eq!(backend.calls.get(), 4);
+}
+
+#[test]
+fn get_connector_gives_up_on_changing_counts() {
+    let backend = Hotplug::new((1..100).collect());
+
+    let mut encoders = Vec::new();
+    let result = mode::get_connector(&backend, 1, None, None, None, Some(&mut encoders), false);
+
+    match result {
+        Err(result::SystemError::Unknown {
+            errno: nix::errno::Errno::EAGAIN,
+        }) => {}
+        other => panic!("expected EAGAIN, got {:?}", other),
+    }
+    // One call to query the counts, then one per attempt
+    assert_eq!(backend.calls.get(), 1 + mode::GET_CONNECTOR_MAX_RETRIES);
+}
@@ -5,7 +5,6 @@ mod utils;
 use utils::*;
 
 use drm::control::Device as ControlDevice;
-use drm::Device as BasicDevice;
 
 use drm::buffer::DrmFourcc;
 
@@ -32,10 +31,8 @@ fn find_prop_id<T: ResourceHandle>(
 pub fn main() {
     let card = Card::open_global();
 
-    card.set_client_capability(drm::ClientCapability::UniversalPlanes, true)
-        .expect("Unable to request UniversalPlanes capability");
-    card.set_client_capability(drm::ClientCapability::Atomic, true)
-        .expect("Unable to request Atomic capability");
+    card.enable_atomic()
+        .expect("Unable to enable atomic modesetting");
 
     // Load the information.
     let res = card
@@ -58,8 +55,11 @@ pub fn main() {
         .find(|&i| i.state() == connector::State::Connected)
         .expect("No connected connectors");
 
-    // Get the first (usually best) mode
-    let &mode = con.modes().get(0).expect("No modes found on connector");
+    // Get the preferred mode, falling back to the first one
+    let mode = con
+        .preferred_mode()
+        .or_else(|| con.modes().first().copied())
+        .expect("No modes found on connector");
 
     let (disp_width, disp_height) = mode.size();
 
@@ -81,15 +81,11 @@ pub fn main() {
         let mut map = card
             .map_dumb_buffer(&mut db)
             .expect("Could not map dumbbuffer");
-        for b in map.as_mut() {
-            *b = 128;
-        }
+        map.fill_u32(0x808080);
     }
 
     // Create an FB:
-    let fb = card
-        .add_framebuffer(&db, 24, 32)
-        .expect("Could not create FB");
+    let fb = card.add_framebuffer_for(&db).expect("Could not create FB");
 
     let planes = card.plane_handles().expect("Could not list planes");
     let (better_planes, compatible_planes): (
@@ -137,7 +133,7 @@ pub fn main() {
     atomic_req.add_property(
         crtc.handle(),
         find_prop_id(&card, crtc.handle(), "MODE_ID").expect("Could not get MODE_ID"),
-        blob,
+        property::Value::Blob(Some(blob)),
     );
     atomic_req.add_property(
         crtc.handle(),
@@ -0,0 +1,59 @@
+extern crate drm;
+extern crate image;
+
+mod utils;
+use utils::*;
+
+use drm::control::Device as ControlDevice;
+
+use drm::buffer::DrmFourcc;
+
+use drm::control::dumbbuffer::DoubleBuffer;
+
+pub fn main() {
+    let card = Card::open_global();
+
+    // Find a connected output and a CRTC able to drive it.
+    let output = card
+        .best_output()
+        .expect("Could not load resources")
+        .expect("No connected output found");
+    let mode = output.mode();
+    let (disp_width, disp_height) = mode.size();
+
+    let mut buffers = DoubleBuffer::new(
+        &card,
+        (disp_width.into(), disp_height.into()),
+        DrmFourcc::Xrgb8888,
+    )
+    .expect("Could not create buffers");
+
+    // Scan out the front buffer first, so that page flips can be queued.
+    // On many setups, this requires root access.
+    card.set_crtc(
+        output.crtc(),
+        Some(buffers.front_framebuffer()),
+        (0, 0),
+        &[output.connector()],
+        Some(mode),
+    )
+    .expect("Could not set CRTC");
+
+    // Fade from black to white over roughly five seconds.
+    let frames = 5 * mode.vrefresh().max(1);
+    for frame in 0..frames {
+        {
+            let mut map = buffers
+                .back_buffer_mut()
+                .expect("Could not map back buffer");
+            let shade = (frame * 255 / frames) as u8;
+            for b in map.as_mut() {
+                *b = shade;
+            }
+        }
+
+        buffers.flip(output.crtc()).expect("Could not flip");
+    }
+
+    buffers.wait_for_flip().expect("Could not wait for flip");
+}
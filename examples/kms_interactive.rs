@@ -38,7 +38,7 @@ fn run_repl(card: &Card) {
             .unwrap();
 
         // Create a Framebuffer to represent it
-        let _fb = card.add_framebuffer(&db, 24, 32).unwrap();
+        let _fb = card.add_framebuffer_for(&db).unwrap();
 
         // Load the image into the buffer
         {
@@ -0,0 +1,94 @@
+extern crate drm;
+extern crate image;
+
+mod utils;
+use utils::*;
+
+use drm::control::Device as ControlDevice;
+
+use drm::buffer::DrmFourcc;
+
+use drm::control::LeaseFlags;
+
+pub fn main() {
+    let card = Card::open_global();
+
+    // Find a connected output and a CRTC able to drive it.
+    let output = card
+        .best_output()
+        .expect("Could not load resources")
+        .expect("No connected output found");
+    let mode = output.mode();
+    let (disp_width, disp_height) = mode.size();
+
+    // Lease the connector and CRTC. The primary plane of the CRTC is leased
+    // along with it, as universal planes are not enabled.
+    // Creating a lease requires being the DRM master.
+    let objects = [output.connector().into(), output.crtc().into()];
+    let (lessee_id, fd) = card
+        .create_lease(&objects, LeaseFlags::CLOEXEC)
+        .expect("Could not create lease");
+
+    // Use the lease in this process instead of passing it to another one.
+    let lessee = Card::from_lease_fd(fd);
+    println!(
+        "Lessee {} has access to {:?}",
+        lessee_id,
+        lessee.get_lease()
+    );
+
+    // The lessee only sees the leased objects, so inspect those without
+    // assuming their type.
+    for object in lessee.get_lease().expect("Could not get lease") {
+        match lessee.get_object_properties(object) {
+            Ok((object_type, props)) => println!(
+                "{:?} {:?} has {} properties",
+                object_type,
+                object,
+                props.as_props_and_values().0.len()
+            ),
+            Err(err) => eprintln!("Could not get properties of {:?}: {}", object, err),
+        }
+    }
+
+    let mut db = lessee
+        .create_dumb_buffer(
+            (disp_width.into(), disp_height.into()),
+            DrmFourcc::Xrgb8888,
+            32,
+        )
+        .expect("Could not create dumb buffer");
+
+    // Map it and paint it blue.
+    {
+        let mut map = lessee
+            .map_dumb_buffer(&mut db)
+            .expect("Could not map dumbbuffer");
+        map.fill_u32(0x0000ff);
+    }
+
+    let fb = lessee
+        .add_framebuffer_for(&db)
+        .expect("Could not create FB");
+
+    // The lessee is the DRM master of the leased objects, so it can set the
+    // CRTC without further privileges.
+    lessee
+        .set_crtc(
+            output.crtc(),
+            Some(fb),
+            (0, 0),
+            &[output.connector()],
+            Some(mode),
+        )
+        .expect("Could not set CRTC");
+
+    let five_seconds = ::std::time::Duration::from_millis(5000);
+    ::std::thread::sleep(five_seconds);
+
+    lessee.destroy_framebuffer(fb).unwrap();
+    lessee.destroy_dumb_buffer(db).unwrap();
+
+    card.revoke_lease(lessee_id)
+        .expect("Could not revoke lease");
+}
@@ -34,13 +34,21 @@ pub fn main() {
         .find(|&i| i.state() == connector::State::Connected)
         .expect("No connected connectors");
 
-    // Get the first (usually best) mode
-    let &mode = con.modes().get(0).expect("No modes found on connector");
+    // Get the preferred mode, falling back to the first one
+    let mode = con
+        .preferred_mode()
+        .or_else(|| con.modes().first().copied())
+        .expect("No modes found on connector");
 
     let (disp_width, disp_height) = mode.size();
 
-    // Find a crtc and FB
-    let crtc = crtcinfo.get(0).expect("No crtcs found");
+    // Find a crtc, preferring one that is not in use
+    let free = card.free_crtcs().expect("Could not load CRTCs");
+    let crtc = crtcinfo
+        .iter()
+        .find(|crtc| free.contains(&crtc.handle()))
+        .or_else(|| crtcinfo.first())
+        .expect("No crtcs found");
 
     // Select the pixel format
     let fmt = DrmFourcc::Xrgb8888;
@@ -57,15 +65,11 @@ pub fn main() {
         let mut map = card
             .map_dumb_buffer(&mut db)
             .expect("Could not map dumbbuffer");
-        for b in map.as_mut() {
-            *b = 128;
-        }
+        map.fill_u32(0x808080);
     }
 
     // Create an FB:
-    let fb = card
-        .add_framebuffer(&db, 24, 32)
-        .expect("Could not create FB");
+    let fb = card.add_framebuffer_for(&db).expect("Could not create FB");
 
     println!("{:#?}", mode);
     println!("{:#?}", fb);
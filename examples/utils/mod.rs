@@ -34,6 +34,13 @@ impl Card {
     pub fn open_global() -> Self {
         Self::open("/dev/dri/card0")
     }
+
+    /// Wraps the file descriptor returned by `create_lease`, so that the lease
+    /// can be used within this process. The lessee shares the device with its
+    /// lessor, but can only access the leased objects.
+    pub fn from_lease_fd(fd: std::os::unix::io::OwnedFd) -> Self {
+        Card(std::fs::File::from(fd))
+    }
 }
 
 pub mod capabilities {
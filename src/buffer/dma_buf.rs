@@ -0,0 +1,126 @@
+//! # Dma-buf
+//!
+//! CPU access to dma-bufs, e.g. imported from a renderer.
+//!
+//! A [`BufferMapping`] is created from the file descriptor of a dma-buf with
+//! [`BufferMapping::new`]. The CPU caches are not coherent with the GPU on
+//! every system, so accesses have to be bracketed by
+//! [`BufferMapping::sync_start`] and [`BufferMapping::sync_end`].
+
+use drm_ffi::dma_buf as ffi;
+use drm_ffi::result::SystemError;
+use std::num::NonZeroUsize;
+use std::os::unix::io::{AsRawFd, BorrowedFd};
+
+/// The kind of CPU access to a [`BufferMapping`]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum SyncAccess {
+    /// The CPU only reads from the buffer
+    Read,
+    /// The CPU only writes to the buffer
+    Write,
+    /// The CPU reads from and writes to the buffer
+    ReadWrite,
+}
+
+impl SyncAccess {
+    fn flags(self) -> u64 {
+        match self {
+            SyncAccess::Read => ffi::DMA_BUF_SYNC_READ,
+            SyncAccess::Write => ffi::DMA_BUF_SYNC_WRITE,
+            SyncAccess::ReadWrite => ffi::DMA_BUF_SYNC_RW,
+        }
+    }
+}
+
+/// Mapping of a dma-buf into the address space of the process
+///
+/// The buffer is unmapped when the mapping is dropped.
+pub struct BufferMapping<'a> {
+    fd: BorrowedFd<'a>,
+    map: &'a mut [u8],
+}
+
+impl<'a> BufferMapping<'a> {
+    /// Maps the first `len` bytes of a dma-buf for CPU access, e.g. to read
+    /// back or software-composite a buffer exported by a renderer.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if `len` is zero or exceeds
+    /// the size of the dma-buf, and [`SystemError::Unsupported`] if the
+    /// exporter does not allow mapping it.
+    pub fn new(fd: BorrowedFd<'a>, len: usize) -> Result<BufferMapping<'a>, SystemError> {
+        use nix::sys::mman;
+
+        // Seeking to the end of a dma-buf reports its size
+        let size = unsafe { nix::libc::lseek(fd.as_raw_fd(), 0, nix::libc::SEEK_END) };
+        let size = nix::errno::Errno::result(size)?;
+        let length = match NonZeroUsize::new(len) {
+            Some(length) if len as u64 <= size as u64 => length,
+            _ => return Err(SystemError::InvalidArgument),
+        };
+
+        let prot = mman::ProtFlags::PROT_READ | mman::ProtFlags::PROT_WRITE;
+        let flags = mman::MapFlags::MAP_SHARED;
+        let map = match unsafe { mman::mmap(None, length, prot, flags, fd.as_raw_fd(), 0) } {
+            Ok(map) => map,
+            // Exporters without CPU access reject the mapping
+            Err(nix::errno::Errno::EINVAL) | Err(nix::errno::Errno::ENODEV) => {
+                return Err(SystemError::Unsupported)
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(BufferMapping {
+            fd,
+            map: unsafe { std::slice::from_raw_parts_mut(map as *mut u8, len) },
+        })
+    }
+
+    /// Prepares the buffer for CPU access, waiting for pending GPU work and
+    /// invalidating the CPU caches as needed.
+    pub fn sync_start(&self, access: SyncAccess) -> Result<(), SystemError> {
+        let flags = ffi::DMA_BUF_SYNC_START | access.flags();
+        ffi::sync(self.fd.as_raw_fd(), flags)?;
+        Ok(())
+    }
+
+    /// Ends CPU access started with [`BufferMapping::sync_start`], flushing
+    /// the CPU caches as needed. `access` has to match the one passed to
+    /// [`BufferMapping::sync_start`].
+    pub fn sync_end(&self, access: SyncAccess) -> Result<(), SystemError> {
+        let flags = ffi::DMA_BUF_SYNC_END | access.flags();
+        ffi::sync(self.fd.as_raw_fd(), flags)?;
+        Ok(())
+    }
+}
+
+impl<'a> std::fmt::Debug for BufferMapping<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("BufferMapping")
+            .field("fd", &self.fd)
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+impl<'a> AsRef<[u8]> for BufferMapping<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.map
+    }
+}
+
+impl<'a> AsMut<[u8]> for BufferMapping<'a> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.map
+    }
+}
+
+impl<'a> Drop for BufferMapping<'a> {
+    fn drop(&mut self) {
+        use nix::sys::mman;
+
+        unsafe {
+            mman::munmap(self.map.as_mut_ptr() as *mut _, self.map.len()).expect("Unmap failed");
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! # Format
+//!
+//! Memory layout information of pixel formats.
+//!
+//! The legacy [`add_framebuffer`](crate::control::Device::add_framebuffer)
+//! path and dumb buffer allocation expect the bits-per-pixel and depth of a
+//! format, while planar formats require the per-plane pixel size and
+//! subsampling to compute correct pitches. [`FormatInfo`] provides these for
+//! the common formats.
+//!
+//! [`FourccExt`](super::FourccExt) computes the pitch and size of a buffer
+//! directly from a [`DrmFourcc`], e.g. to plan allocations or validate
+//! imported buffers.
+
+use super::DrmFourcc;
+
+/// Layout information of a pixel format
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct FormatInfo {
+    pub(crate) format: DrmFourcc,
+    pub(crate) depth: u32,
+    pub(crate) num_planes: usize,
+    pub(crate) cpp: [u32; 4],
+    pub(crate) hsub: u32,
+    pub(crate) vsub: u32,
+}
+
+impl FormatInfo {
+    const fn rgb(format: DrmFourcc, depth: u32, cpp: u32) -> Self {
+        FormatInfo {
+            format,
+            depth,
+            num_planes: 1,
+            cpp: [cpp, 0, 0, 0],
+            hsub: 1,
+            vsub: 1,
+        }
+    }
+
+    const fn yuv(format: DrmFourcc, cpp: &[u32], hsub: u32, vsub: u32) -> Self {
+        let mut planes = [0; 4];
+        let mut i = 0;
+        while i < cpp.len() {
+            planes[i] = cpp[i];
+            i += 1;
+        }
+
+        FormatInfo {
+            format,
+            depth: 0,
+            num_planes: cpp.len(),
+            cpp: planes,
+            hsub,
+            vsub,
+        }
+    }
+
+    /// Returns the layout information of a format, or [`None`] if the format
+    /// is not known to this crate.
+    pub fn new(format: DrmFourcc) -> Option<FormatInfo> {
+        use self::DrmFourcc as F;
+
+        // source: formats[] in drivers/gpu/drm/drm_fourcc.c
+        let info = match format {
+            F::C8 | F::R8 | F::Rgb332 | F::Bgr233 => Self::rgb(format, 8, 1),
+            F::R16 | F::Rg88 | F::Gr88 => Self::rgb(format, 16, 2),
+            F::Xrgb4444 | F::Xbgr4444 | F::Rgbx4444 | F::Bgrx4444 => Self::rgb(format, 0, 2),
+            F::Argb4444 | F::Abgr4444 | F::Rgba4444 | F::Bgra4444 => Self::rgb(format, 0, 2),
+            F::Xrgb1555 | F::Xbgr1555 | F::Rgbx5551 | F::Bgrx5551 => Self::rgb(format, 15, 2),
+            F::Argb1555 | F::Abgr1555 | F::Rgba5551 | F::Bgra5551 => Self::rgb(format, 15, 2),
+            F::Rgb565 | F::Bgr565 => Self::rgb(format, 16, 2),
+            F::Rgb888 | F::Bgr888 => Self::rgb(format, 24, 3),
+            F::Xrgb8888 | F::Xbgr8888 | F::Rgbx8888 | F::Bgrx8888 => Self::rgb(format, 24, 4),
+            F::Argb8888 | F::Abgr8888 | F::Rgba8888 | F::Bgra8888 => Self::rgb(format, 32, 4),
+            F::Xrgb2101010 | F::Xbgr2101010 | F::Rgbx1010102 | F::Bgrx1010102 => {
+                Self::rgb(format, 30, 4)
+            }
+            F::Argb2101010 | F::Abgr2101010 | F::Rgba1010102 | F::Bgra1010102 => {
+                Self::rgb(format, 30, 4)
+            }
+            F::Rg1616 | F::Gr1616 => Self::rgb(format, 32, 4),
+            F::Xrgb16161616f | F::Xbgr16161616f | F::Argb16161616f | F::Abgr16161616f => {
+                Self::rgb(format, 0, 8)
+            }
+            F::Yuyv | F::Yvyu | F::Uyvy | F::Vyuy => Self::yuv(format, &[2], 2, 1),
+            F::Ayuv | F::Xyuv8888 => Self::yuv(format, &[4], 1, 1),
+            F::Vuy888 => Self::yuv(format, &[3], 1, 1),
+            F::Nv12 | F::Nv21 => Self::yuv(format, &[1, 2], 2, 2),
+            F::Nv16 | F::Nv61 => Self::yuv(format, &[1, 2], 2, 1),
+            F::Nv24 | F::Nv42 => Self::yuv(format, &[1, 2], 1, 1),
+            F::P010 | F::P012 | F::P016 => Self::yuv(format, &[2, 4], 2, 2),
+            F::P210 => Self::yuv(format, &[2, 4], 2, 1),
+            F::Yuv410 | F::Yvu410 => Self::yuv(format, &[1, 1, 1], 4, 4),
+            F::Yuv411 | F::Yvu411 => Self::yuv(format, &[1, 1, 1], 4, 1),
+            F::Yuv420 | F::Yvu420 => Self::yuv(format, &[1, 1, 1], 2, 2),
+            F::Yuv422 | F::Yvu422 => Self::yuv(format, &[1, 1, 1], 2, 1),
+            F::Yuv444 | F::Yvu444 => Self::yuv(format, &[1, 1, 1], 1, 1),
+            _ => return None,
+        };
+
+        Some(info)
+    }
+
+    /// Returns the format this information describes.
+    pub fn format(&self) -> DrmFourcc {
+        self.format
+    }
+
+    /// Returns the color depth used by the legacy framebuffer API, or [`None`]
+    /// if the format has no legacy equivalent.
+    pub fn depth(&self) -> Option<u32> {
+        match self.depth {
+            0 => None,
+            depth => Some(depth),
+        }
+    }
+
+    /// Returns the bits-per-pixel of the first plane.
+    pub fn bpp(&self) -> u32 {
+        self.cpp[0] * 8
+    }
+
+    /// Returns the number of planes of the format.
+    pub fn plane_count(&self) -> usize {
+        self.num_planes
+    }
+
+    /// Returns the bytes-per-pixel of a plane, or `0` if the plane does not
+    /// exist.
+    pub fn cpp(&self, plane: usize) -> u32 {
+        self.cpp.get(plane).copied().unwrap_or(0)
+    }
+
+    /// Returns the horizontal and vertical subsampling factors of the
+    /// chroma planes.
+    pub fn subsampling(&self) -> (u32, u32) {
+        (self.hsub, self.vsub)
+    }
+
+    /// Returns the size in pixels of a plane for a buffer of the given size,
+    /// taking subsampling into account.
+    pub fn plane_size(&self, plane: usize, size: (u32, u32)) -> (u32, u32) {
+        if plane == 0 {
+            return size;
+        }
+
+        let (w, h) = size;
+        (
+            (w + self.hsub - 1) / self.hsub,
+            (h + self.vsub - 1) / self.vsub,
+        )
+    }
+
+    /// Returns the minimal pitch in bytes of a plane for a buffer of the given
+    /// width.
+    pub fn min_pitch(&self, plane: usize, width: u32) -> u32 {
+        self.plane_size(plane, (width, 1)).0 * self.cpp(plane)
+    }
+
+    /// Returns the layout of a tightly packed buffer of the given size, with
+    /// all planes stored one after another using their minimal pitch.
+    pub fn layout(&self, size: (u32, u32)) -> BufferLayout {
+        let mut layout = BufferLayout {
+            pitches: [0; 4],
+            offsets: [0; 4],
+            size: 0,
+        };
+
+        for plane in 0..self.num_planes {
+            let pitch = self.min_pitch(plane, size.0);
+            let (_, height) = self.plane_size(plane, size);
+
+            layout.pitches[plane] = pitch;
+            layout.offsets[plane] = layout.size;
+            layout.size += u64::from(pitch) * u64::from(height);
+        }
+
+        layout
+    }
+}
+
+/// The memory layout of a buffer, see [`FormatInfo::layout`]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct BufferLayout {
+    pub(crate) pitches: [u32; 4],
+    pub(crate) offsets: [u64; 4],
+    pub(crate) size: u64,
+}
+
+impl BufferLayout {
+    /// Returns the pitches of the planes, `0` for planes that do not exist.
+    pub fn pitches(&self) -> [u32; 4] {
+        self.pitches
+    }
+
+    /// Returns the byte offsets of the planes from the start of the buffer,
+    /// `0` for planes that do not exist.
+    pub fn offsets(&self) -> [u64; 4] {
+        self.offsets
+    }
+
+    /// Returns the combined size in bytes of all planes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
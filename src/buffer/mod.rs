@@ -24,9 +24,100 @@
 //! like a regular one. This allows better control and security, and is the
 //! recommended method of sharing buffers.
 
+pub mod dma_buf;
+pub mod format;
+
 use control;
 pub use drm_fourcc::{DrmFourcc, DrmModifier, DrmVendor, UnrecognizedFourcc, UnrecognizedVendor};
 
+/// Extensions to [`DrmFourcc`]
+///
+/// [`DrmFourcc`] implements [`TryFrom<u32>`](std::convert::TryFrom), which
+/// fails for codes the enum does not cover, and [`Display`](std::fmt::Display),
+/// which prints the four characters of the code.
+///
+/// ```
+/// use drm::buffer::{DrmFourcc, FourccExt};
+///
+/// assert_eq!(DrmFourcc::from_raw_lossy(0x34325258), Some(DrmFourcc::Xrgb8888));
+/// assert_eq!(DrmFourcc::from_raw_lossy(0), None);
+/// assert_eq!(DrmFourcc::Xrgb8888.as_str(), "XR24");
+/// ```
+///
+/// The pitch and size calculations return [`None`] if the format is not known
+/// to [`format::FormatInfo`].
+///
+/// ```
+/// use drm::buffer::{DrmFourcc, FourccExt};
+///
+/// assert_eq!(DrmFourcc::Xrgb8888.minimum_pitch(640), Some(2560));
+/// assert_eq!(DrmFourcc::Xrgb8888.buffer_size(640, 480), Some(1_228_800));
+///
+/// assert_eq!(DrmFourcc::Yuyv.minimum_pitch(640), Some(1280));
+/// assert_eq!(DrmFourcc::Yuyv.buffer_size(640, 480), Some(614_400));
+///
+/// // Luma plane followed by a half-height chroma plane
+/// let nv12 = DrmFourcc::Nv12.layout(641, 481).unwrap();
+/// assert_eq!(nv12.pitches(), [641, 642, 0, 0]);
+/// assert_eq!(nv12.offsets(), [0, 308_321, 0, 0]);
+/// assert_eq!(nv12.size(), 463_043);
+/// ```
+pub trait FourccExt: Sized {
+    /// Converts a raw fourcc code, returning [`None`] for codes unknown to
+    /// [`DrmFourcc`], e.g. driver specific or newer formats.
+    ///
+    /// Format lists read from the kernel skip such formats rather than
+    /// failing.
+    fn from_raw_lossy(raw: u32) -> Option<Self>;
+
+    /// Returns the four characters of the code, e.g. `"XR24"`.
+    ///
+    /// Characters that are not printable ASCII, e.g. in
+    /// [`DrmFourcc::Big_endian`] which is a flag rather than a format, are
+    /// replaced by spaces.
+    fn as_str(&self) -> String;
+
+    /// Returns the minimal pitch in bytes of the first plane for a buffer of
+    /// the given width.
+    fn minimum_pitch(&self, width: u32) -> Option<u32>;
+
+    /// Returns the combined size in bytes of all planes of a tightly packed
+    /// buffer of the given size.
+    fn buffer_size(&self, width: u32, height: u32) -> Option<u64>;
+
+    /// Returns the pitches, plane offsets and size of a tightly packed buffer
+    /// of the given size.
+    fn layout(&self, width: u32, height: u32) -> Option<format::BufferLayout>;
+}
+
+impl FourccExt for DrmFourcc {
+    fn from_raw_lossy(raw: u32) -> Option<Self> {
+        use std::convert::TryFrom;
+
+        DrmFourcc::try_from(raw).ok()
+    }
+
+    fn as_str(&self) -> String {
+        (*self as u32)
+            .to_le_bytes()
+            .iter()
+            .map(|&c| if c.is_ascii_graphic() { c as char } else { ' ' })
+            .collect()
+    }
+
+    fn minimum_pitch(&self, width: u32) -> Option<u32> {
+        format::FormatInfo::new(*self).map(|info| info.min_pitch(0, width))
+    }
+
+    fn buffer_size(&self, width: u32, height: u32) -> Option<u64> {
+        self.layout(width, height).map(|layout| layout.size())
+    }
+
+    fn layout(&self, width: u32, height: u32) -> Option<format::BufferLayout> {
+        format::FormatInfo::new(*self).map(|info| info.layout((width, height)))
+    }
+}
+
 /// A handle to a GEM buffer
 ///
 /// # Notes
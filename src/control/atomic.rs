@@ -1,14 +1,17 @@
 //! Helpers for atomic modesetting.
 
 use control;
+use drm_ffi::result::SystemError;
 
 /// Helper struct to construct atomic commit requests
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
 pub struct AtomicModeReq {
     pub(super) objects: Vec<control::RawResourceHandle>,
     pub(super) count_props_per_object: Vec<u32>,
     pub(super) props: Vec<control::property::Handle>,
     pub(super) values: Vec<control::property::RawValue>,
+    pub(super) out_fences: Vec<(control::crtc::Handle, control::property::Handle)>,
+    pub(crate) plane_states: Vec<(control::plane::Handle, control::plane::PlaneState)>,
 }
 
 impl AtomicModeReq {
@@ -68,4 +71,500 @@ impl AtomicModeReq {
     {
         self.add_raw_property(handle.into(), property, value.into())
     }
+
+    /// Requests an out-fence for a CRTC, signalled once the commit has been
+    /// applied to it.
+    ///
+    /// The fences are only returned by
+    /// [`Device::atomic_commit_with_out_fences`](control::Device::atomic_commit_with_out_fences),
+    /// which points the `OUT_FENCE_PTR` of every requested CRTC at its own
+    /// slot. Requesting a fence for the same CRTC twice has no further effect.
+    /// Returns [`SystemError::Unsupported`] if the CRTC does not expose the
+    /// property.
+    pub fn request_out_fence<D>(
+        &mut self,
+        card: &D,
+        crtc: control::crtc::Handle,
+    ) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let prop = control::property_handle(card, crtc, "OUT_FENCE_PTR")?;
+        if !self.out_fences.iter().any(|&(other, _)| other == crtc) {
+            self.out_fences.push((crtc, prop));
+        }
+        Ok(())
+    }
+
+    /// Adds a property of an object by name, resolving it through `table`.
+    ///
+    /// Values of unsigned range properties are clamped to their range.
+    /// Returns [`SystemError::Unsupported`] if the object does not have the
+    /// property.
+    pub fn set_named_property<T>(
+        &mut self,
+        table: &control::property::PropertyTable,
+        object: T,
+        name: &str,
+        value: control::property::RawValue,
+    ) -> Result<(), SystemError>
+    where
+        T: control::ResourceHandle,
+    {
+        let info = table.get(object, name).ok_or(SystemError::Unsupported)?;
+        self.add_raw_property(
+            object.into(),
+            info.handle(),
+            control::clamp_to_range(info, value),
+        );
+        Ok(())
+    }
+
+    /// Sets the `zpos` of a plane, resolving the property through `table`.
+    ///
+    /// Planes with a higher `zpos` are stacked on top. The value is clamped
+    /// to the range supported by the plane. Returns
+    /// [`SystemError::Unsupported`] if the plane does not expose the property.
+    pub fn set_zpos(
+        &mut self,
+        table: &control::property::PropertyTable,
+        plane: control::plane::Handle,
+        zpos: u64,
+    ) -> Result<(), SystemError> {
+        self.set_named_property(table, plane, "zpos", zpos)
+    }
+
+    /// Returns whether committing this request changes the display
+    /// configuration and thus needs [`control::AtomicCommitFlags::ALLOW_MODESET`].
+    ///
+    /// This checks whether the request sets a CRTC's `MODE_ID` or `ACTIVE`, or
+    /// a connector's `CRTC_ID`, to a value differing from the current state
+    /// of the device. A new `MODE_ID` blob holding the current mode is not a
+    /// change. Drivers can require a modeset for other changes too, e.g. if
+    /// the bandwidth needed by the planes changes, which is only detected by
+    /// committing the request with [`control::AtomicCommitFlags::TEST_ONLY`].
+    /// Requests only flipping planes to new framebuffers usually do not need
+    /// a modeset and can be committed with
+    /// [`control::AtomicCommitFlags::NONBLOCK`].
+    pub fn requires_modeset<D>(&self, card: &D) -> Result<bool, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let res = card.resource_handles()?;
+        let mut start = 0;
+
+        for (&object, &count) in self.objects.iter().zip(&self.count_props_per_object) {
+            let end = start + count as usize;
+            let props = &self.props[start..end];
+            let values = &self.values[start..end];
+            start = end;
+
+            let current = card.get_raw_properties(object, control::ObjectType::Any)?;
+            let is_connector = res
+                .connectors()
+                .iter()
+                .any(|&conn| control::RawResourceHandle::from(conn) == object);
+
+            for (&prop, &value) in props.iter().zip(values) {
+                let info = card.get_property(prop)?;
+                let is_mode = info.name().to_bytes() == b"MODE_ID";
+                let affects_mode = match info.name().to_bytes() {
+                    b"MODE_ID" | b"ACTIVE" => true,
+                    b"CRTC_ID" => is_connector,
+                    _ => false,
+                };
+                if !affects_mode {
+                    continue;
+                }
+
+                let changed = match current.iter().find(|&(&id, _)| id == prop) {
+                    Some((_, &current)) if current == value => false,
+                    // Every blob has its own id, the kernel compares the modes
+                    Some((_, &current)) if is_mode => !same_blob_data(card, current, value)?,
+                    _ => true,
+                };
+                if changed {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns whether this request can be committed with
+    /// [`control::AtomicCommitFlags::PAGE_FLIP_ASYNC`].
+    ///
+    /// Asynchronous commits may only change the `FB_ID` of planes. Any other
+    /// property has to be set to its current value. Drivers can impose
+    /// further restrictions, e.g. only allow flipping primary planes.
+    pub fn is_async_compatible<D>(&self, card: &D) -> Result<bool, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let mut start = 0;
+
+        for (&object, &count) in self.objects.iter().zip(&self.count_props_per_object) {
+            let end = start + count as usize;
+            let props = &self.props[start..end];
+            let values = &self.values[start..end];
+            start = end;
+
+            let current = card.get_raw_properties(object, control::ObjectType::Any)?;
+
+            for (&prop, &value) in props.iter().zip(values) {
+                let info = card.get_property(prop)?;
+                let unchanged = current
+                    .iter()
+                    .any(|(&id, &current)| id == prop && current == value);
+                if info.name().to_bytes() != b"FB_ID" && !unchanged {
+                    return Ok(false);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Adds the properties needed to turn off a CRTC to the request.
+    ///
+    /// Sets `ACTIVE` and `MODE_ID` of the CRTC to zero and detaches every
+    /// connector and plane currently bound to it, as the kernel rejects
+    /// commits leaving enabled planes or connectors on a disabled CRTC. The
+    /// bindings are read from the current state of the device, so entries
+    /// of this request attaching other objects to the CRTC are not taken
+    /// into account.
+    ///
+    /// The commit needs [`control::AtomicCommitFlags::ALLOW_MODESET`].
+    /// Returns [`SystemError::Unsupported`] if the CRTC does not expose atomic
+    /// properties.
+    pub fn disable_crtc<D>(
+        &mut self,
+        card: &D,
+        crtc: control::crtc::Handle,
+    ) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let active = control::find_property(card, crtc, &["ACTIVE"])?;
+        let mode_id = control::find_property(card, crtc, &["MODE_ID"])?;
+        let (active, mode_id) = match (active, mode_id) {
+            (Some((active, _)), Some((mode_id, _))) => (active, mode_id),
+            _ => return Err(SystemError::Unsupported),
+        };
+        self.add_raw_property(crtc.into(), active.handle(), 0);
+        self.add_raw_property(crtc.into(), mode_id.handle(), 0);
+
+        let crtc_id = u64::from(u32::from(crtc));
+        let res = card.resource_handles()?;
+        for &connector in res.connectors() {
+            if let Some((prop, value)) = control::find_property(card, connector, &["CRTC_ID"])? {
+                if value == crtc_id {
+                    self.add_raw_property(connector.into(), prop.handle(), 0);
+                }
+            }
+        }
+
+        for plane in card.plane_handles()? {
+            let bound = match control::find_property(card, plane, &["CRTC_ID"])? {
+                Some((prop, value)) if value == crtc_id => prop,
+                _ => continue,
+            };
+            self.add_raw_property(plane.into(), bound.handle(), 0);
+            if let Some((fb, _)) = control::find_property(card, plane, &["FB_ID"])? {
+                self.add_raw_property(plane.into(), fb.handle(), 0);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves a connector from one CRTC to another, driving it with `mode`.
+    ///
+    /// The connector's `CRTC_ID` is pointed at `to`, which is activated with
+    /// `mode`. If no other connector remains on `from`, it is turned off
+    /// along with its planes, see [`AtomicModeReq::disable_crtc`]. The planes
+    /// of `to` are not touched, so they should be added to the request before
+    /// calling this.
+    ///
+    /// The resulting request is checked with a test commit. If the kernel
+    /// rejects it, the request is left unchanged and the error is returned.
+    /// On success, returns the handle of the property blob created for `mode`,
+    /// which should be destroyed with
+    /// [`control::Device::destroy_property_blob`] once the request is
+    /// committed.
+    pub fn reroute<D>(
+        &mut self,
+        card: &D,
+        connector: control::connector::Handle,
+        from: control::crtc::Handle,
+        to: control::crtc::Handle,
+        mode: control::Mode,
+    ) -> Result<control::blob::Handle, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let mut req = self.clone();
+
+        let from_id = u64::from(u32::from(from));
+        let mut shared = false;
+        for &other in card.resource_handles()?.connectors() {
+            if other == connector {
+                continue;
+            }
+            if let Some((_, value)) = control::find_property(card, other, &["CRTC_ID"])? {
+                shared |= value == from_id;
+            }
+        }
+        if !shared && from != to {
+            req.disable_crtc(card, from)?;
+        }
+
+        let crtc_id = control::property_handle(card, connector, "CRTC_ID")?;
+        let active = control::property_handle(card, to, "ACTIVE")?;
+        let mode_id = control::property_handle(card, to, "MODE_ID")?;
+        let blob = card.create_property_blob(&mode)?;
+        req.add_raw_property(connector.into(), crtc_id, u32::from(to).into());
+        req.add_raw_property(to.into(), active, 1);
+        req.add_property(to, mode_id, control::property::Value::Blob(Some(blob)));
+
+        let flags =
+            control::AtomicCommitFlags::TEST_ONLY | control::AtomicCommitFlags::ALLOW_MODESET;
+        if let Err(err) = card.atomic_commit(flags, req.clone()) {
+            let _ = card.destroy_property_blob(blob);
+            return Err(err);
+        }
+
+        *self = req;
+        Ok(blob)
+    }
+
+    /// Sets a property of a connector.
+    ///
+    /// Unlike the legacy setters of [`control::Device`], which issue one ioctl
+    /// per property, settings added to the same request are applied together
+    /// with the next commit. Some settings, e.g.
+    /// [`control::connector::Setting::MaxBpc`], may need
+    /// [`control::AtomicCommitFlags::ALLOW_MODESET`].
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector does not expose
+    /// the property, or [`SystemError::InvalidArgument`] if it does not
+    /// support the value.
+    pub fn set_connector_property<D>(
+        &mut self,
+        card: &D,
+        connector: control::connector::Handle,
+        setting: control::connector::Setting,
+    ) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        use control::connector::Setting;
+
+        let (prop, value) = match setting {
+            Setting::ScalingMode(mode) => control::lookup_enum_property(card, connector, mode)?,
+            Setting::BroadcastRgb(range) => control::lookup_enum_property(card, connector, range)?,
+            Setting::Colorspace(colorspace) => {
+                control::lookup_enum_property(card, connector, colorspace)?
+            }
+            Setting::HdcpContentType(content_type) => {
+                control::lookup_enum_property(card, connector, content_type)?
+            }
+            Setting::ContentType(content_type) => {
+                control::lookup_enum_property(card, connector, content_type)?
+            }
+            Setting::Underscan(mode) => control::lookup_enum_property(card, connector, mode)?,
+            Setting::SelectSubconnector(subconnector) => {
+                control::lookup_enum_property(card, connector, subconnector)?
+            }
+            Setting::MaxBpc(bpc) => {
+                let (info, _) = control::find_property(
+                    card,
+                    connector,
+                    control::connector::MAX_BPC_PROPERTY_NAMES,
+                )?
+                .ok_or(SystemError::Unsupported)?;
+                (info.handle(), control::clamp_to_range(&info, bpc))
+            }
+        };
+
+        self.add_raw_property(connector.into(), prop, value);
+        Ok(())
+    }
+
+    /// Moves a plane to the desired state, only adding the properties that
+    /// differ from the state last committed through `cache`.
+    ///
+    /// Once the request has been committed successfully, pass it to
+    /// [`control::plane::PlaneStateCache::update`]. Returns
+    /// [`SystemError::Unsupported`] if the plane does not expose atomic
+    /// properties.
+    pub fn set_plane_state<D>(
+        &mut self,
+        card: &D,
+        cache: &control::plane::PlaneStateCache,
+        plane: control::plane::Handle,
+        desired: control::plane::PlaneState,
+    ) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        for (prop, value) in cache.changes(card, plane, &desired)? {
+            self.add_raw_property(plane.into(), prop, value);
+        }
+
+        self.plane_states.retain(|&(other, _)| other != plane);
+        self.plane_states.push((plane, desired));
+        Ok(())
+    }
+
+    /// Sets the color shown where no plane covers a CRTC.
+    ///
+    /// `color` uses the ARGB64 layout with 16 bits per channel, see
+    /// [`control::crtc::argb64`]. Painting the background this way avoids
+    /// allocating a full-screen plane, e.g. to letterbox content. Returns
+    /// [`SystemError::Unsupported`] if the CRTC does not expose a background
+    /// color property.
+    pub fn set_background_color<D>(
+        &mut self,
+        card: &D,
+        crtc: control::crtc::Handle,
+        color: u64,
+    ) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let (info, _) = control::find_property(card, crtc, control::crtc::BACKGROUND_COLOR)?
+            .ok_or(SystemError::Unsupported)?;
+        self.add_raw_property(crtc.into(), info.handle(), color);
+        Ok(())
+    }
+
+    /// Sets the rotation and reflection of a plane.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] without changing the request
+    /// if `rotation` does not contain exactly one angle, or if the plane does
+    /// not support it, see [`control::plane::Info::supported_rotations`].
+    /// Planes without a `rotation` property accept
+    /// [`control::plane::Rotation::ROTATE_0`], which adds nothing.
+    pub fn set_rotation<D>(
+        &mut self,
+        card: &D,
+        plane: control::plane::Handle,
+        rotation: control::plane::Rotation,
+    ) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        use control::plane::Rotation;
+
+        let angles =
+            Rotation::ROTATE_0 | Rotation::ROTATE_90 | Rotation::ROTATE_180 | Rotation::ROTATE_270;
+        if (rotation & angles).bits().count_ones() != 1 {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        match control::plane::rotation_property(card, plane)? {
+            Some((prop, supported)) if supported.contains(rotation) => {
+                self.add_raw_property(plane.into(), prop, rotation.bits().into());
+                Ok(())
+            }
+            None if rotation == Rotation::ROTATE_0 => Ok(()),
+            _ => Err(SystemError::InvalidArgument),
+        }
+    }
+
+    /// Returns the object, property and value of every entry of the request,
+    /// in the order they are passed to the kernel.
+    pub(crate) fn entries(
+        &self,
+    ) -> Vec<(
+        control::RawResourceHandle,
+        control::property::Handle,
+        control::property::RawValue,
+    )> {
+        let mut entries = Vec::with_capacity(self.props.len());
+        let mut start = 0;
+        for (&obj, &count) in self.objects.iter().zip(&self.count_props_per_object) {
+            let end = start + count as usize;
+            for (&prop, &value) in self.props[start..end].iter().zip(&self.values[start..end]) {
+                entries.push((obj, prop, value));
+            }
+            start = end;
+        }
+        entries
+    }
+}
+
+/// Returns whether two blob property values refer to blobs with the same
+/// data. Unset blobs only equal each other.
+fn same_blob_data<D>(
+    card: &D,
+    a: control::property::RawValue,
+    b: control::property::RawValue,
+) -> Result<bool, SystemError>
+where
+    D: control::Device + ?Sized,
+{
+    let blob = |value| control::from_u32::<control::blob::Handle>(value as u32);
+    match (blob(a), blob(b)) {
+        (Some(a), Some(b)) => Ok(card.get_property_blob(a)? == card.get_property_blob(b)?),
+        (a, b) => Ok(a == b),
+    }
+}
+
+/// The way an atomic request was committed, see
+/// [`control::Device::atomic_commit_with_escalation`].
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum CommitPath {
+    /// The request was committed with the given flags
+    FastPath,
+    /// The request was rejected and then committed with
+    /// [`control::AtomicCommitFlags::ALLOW_MODESET`] added
+    Escalated,
+}
+
+/// Report of an atomic request rejected by the kernel, see
+/// [`control::Device::debug_atomic`].
+#[derive(Debug)]
+pub struct AtomicTestFailure {
+    pub(crate) error: SystemError,
+    pub(crate) culprit: Option<(
+        control::RawResourceHandle,
+        control::property::Handle,
+        control::property::RawValue,
+    )>,
+    pub(crate) accepted: usize,
+}
+
+impl AtomicTestFailure {
+    /// Returns the error the kernel reported for the smallest rejected part of
+    /// the request.
+    pub fn error(&self) -> &SystemError {
+        &self.error
+    }
+
+    /// Returns the object of the first entry that caused the rejection.
+    ///
+    /// Returns [`None`] if even an empty request is rejected, e.g. because
+    /// atomic modesetting is not enabled.
+    pub fn object(&self) -> Option<control::RawResourceHandle> {
+        self.culprit.map(|(obj, _, _)| obj)
+    }
+
+    /// Returns the property of the first entry that caused the rejection.
+    pub fn property(&self) -> Option<control::property::Handle> {
+        self.culprit.map(|(_, prop, _)| prop)
+    }
+
+    /// Returns the value of the first entry that caused the rejection.
+    pub fn value(&self) -> Option<control::property::RawValue> {
+        self.culprit.map(|(_, _, value)| value)
+    }
+
+    /// Returns the number of entries preceding the culprit, which the kernel
+    /// accepted on their own.
+    pub fn accepted(&self) -> usize {
+        self.accepted
+    }
 }
@@ -0,0 +1,48 @@
+//! # Property Blob
+//!
+//! Opaque data blobs referenced by the value of blob properties, such as the
+//! `MODE_ID` of a CRTC or the `EDID` of a connector.
+
+use control;
+use drm_ffi as ffi;
+
+/// A handle to a property blob
+///
+/// Blob ids share their namespace with other mode objects, but are only
+/// meaningful to [`control::Device::get_property_blob`] and
+/// [`control::Device::destroy_property_blob`].
+#[repr(transparent)]
+#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+pub struct Handle(control::RawResourceHandle);
+
+// Safety: Handle is repr(transparent) over NonZeroU32
+unsafe impl bytemuck::ZeroableInOption for Handle {}
+unsafe impl bytemuck::PodInOption for Handle {}
+
+impl From<Handle> for control::RawResourceHandle {
+    fn from(handle: Handle) -> Self {
+        handle.0
+    }
+}
+
+impl From<Handle> for u32 {
+    fn from(handle: Handle) -> Self {
+        handle.0.into()
+    }
+}
+
+impl From<control::RawResourceHandle> for Handle {
+    fn from(handle: control::RawResourceHandle) -> Self {
+        Handle(handle)
+    }
+}
+
+impl control::ResourceHandle for Handle {
+    const FFI_TYPE: u32 = ffi::DRM_MODE_OBJECT_BLOB;
+}
+
+impl std::fmt::Debug for Handle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("blob::Handle").field(&self.0).finish()
+    }
+}
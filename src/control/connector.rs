@@ -8,6 +8,8 @@
 
 use control;
 use drm_ffi as ffi;
+use drm_ffi::result::SystemError;
+use std::collections::HashMap;
 
 /// A handle to a connector
 #[repr(transparent)]
@@ -65,6 +67,22 @@ impl Info {
         self.handle
     }
 
+    /// Returns the properties of this connector along with their decoded values,
+    /// keyed by property name.
+    ///
+    /// Use [`control::Device::get_named_properties`] with a shared
+    /// [`control::property::InfoCache`] when reading the properties of
+    /// several objects.
+    pub fn properties<D>(
+        &self,
+        card: &D,
+    ) -> Result<HashMap<String, control::dump::ValueDump>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        card.get_named_properties(self.handle, &control::property::InfoCache::new(card))
+    }
+
     /// Returns the type of `Interface` of this connector.
     pub fn interface(&self) -> Interface {
         self.interface
@@ -98,10 +116,723 @@ impl Info {
         &self.modes
     }
 
+    /// Returns the mode the display prefers, usually its native resolution.
+    ///
+    /// Returns [`None`] if no mode is flagged as preferred.
+    pub fn preferred_mode(&self) -> Option<control::Mode> {
+        self.modes.iter().copied().find(control::Mode::is_preferred)
+    }
+
+    /// Returns whether this connector advertises a mode with the same timings
+    /// as `mode`, see [`control::Mode::timing_eq`].
+    ///
+    /// This is a cheap check against [`Info::modes`]. Custom modes, e.g.
+    /// generated CVT modes, may still be accepted by the driver, which
+    /// [`Info::validate_mode`] checks.
+    pub fn supports_mode(&self, mode: &control::Mode) -> bool {
+        self.modes.iter().any(|known| known.timing_eq(mode))
+    }
+
+    /// Asks the driver whether `mode` can be set on this connector, using a
+    /// [`AtomicCommitFlags::TEST_ONLY`](control::AtomicCommitFlags::TEST_ONLY)
+    /// commit.
+    ///
+    /// The test routes the connector to its current CRTC, or to the first CRTC
+    /// its encoders can drive, and sets `mode` on it. Nothing is applied.
+    /// Returns [`SystemError::InvalidArgument`] if no CRTC can drive the
+    /// connector or the driver rejects the mode.
+    pub fn validate_mode<D>(&self, card: &D, mode: &control::Mode) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let crtc = match self.current_crtc(card)? {
+            Some(crtc) => crtc,
+            None => {
+                let resources = card.resource_handles()?;
+                let mut crtcs = Vec::new();
+                for &encoder in &self.encoders {
+                    let filter = card.get_encoder(encoder)?.possible_crtcs();
+                    crtcs.extend(resources.filter_crtcs(filter));
+                }
+                *crtcs.first().ok_or(SystemError::InvalidArgument)?
+            }
+        };
+
+        let crtc_id = control::property_handle(card, self.handle, "CRTC_ID")?;
+        let active = control::property_handle(card, crtc, "ACTIVE")?;
+        let mode_id = control::property_handle(card, crtc, "MODE_ID")?;
+        let blob = card.create_property_blob(mode)?;
+
+        let mut req = control::atomic::AtomicModeReq::new();
+        req.add_property(
+            self.handle,
+            crtc_id,
+            control::property::Value::CRTC(Some(crtc)),
+        );
+        req.add_property(crtc, active, control::property::Value::Boolean(true));
+        req.add_property(crtc, mode_id, control::property::Value::Blob(Some(blob)));
+
+        let flags =
+            control::AtomicCommitFlags::TEST_ONLY | control::AtomicCommitFlags::ALLOW_MODESET;
+        let result = card.atomic_commit(flags, req);
+        let _ = card.destroy_property_blob(blob);
+        result
+    }
+
     /// Returns the current encoder attached to this connector.
     pub fn current_encoder(&self) -> Option<control::encoder::Handle> {
         self.curr_enc
     }
+
+    /// Returns the CRTC this connector is currently routed to, as read from
+    /// its atomic `CRTC_ID` property.
+    ///
+    /// Unlike following [`Info::current_encoder`], this reflects the routing
+    /// of the atomic state, see also
+    /// [`Device::connectors_for_crtc`](control::Device::connectors_for_crtc).
+    /// Returns [`None`] if the connector is not routed, and
+    /// [`SystemError::Unsupported`] if the driver does not expose the property.
+    pub fn current_crtc<D>(&self, card: &D) -> Result<Option<control::crtc::Handle>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        match control::find_property(card, self.handle, &["CRTC_ID"])? {
+            Some((_, value)) => Ok(control::from_u32(value as u32)),
+            None => Err(SystemError::Unsupported),
+        }
+    }
+
+    /// Returns the scaling modes supported by this connector.
+    ///
+    /// Connectors without a scaling mode property return an empty list.
+    pub fn scaling_modes<D>(&self, card: &D) -> Result<Vec<ScalingMode>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::enum_property_values(card, self.handle)
+    }
+
+    /// Returns the content protection (HDCP) state of this connector.
+    ///
+    /// Returns [`None`] if the driver or connector does not support HDCP.
+    pub fn content_protection<D>(&self, card: &D) -> Result<Option<ContentProtection>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::get_enum_property(card, self.handle)
+    }
+
+    /// Returns the HDCP content type requested for this connector.
+    ///
+    /// Returns [`None`] if the driver or connector does not support selecting
+    /// the content type.
+    pub fn hdcp_content_type<D>(&self, card: &D) -> Result<Option<HdcpContentType>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::get_enum_property(card, self.handle)
+    }
+
+    /// Returns the type of content signalled to the display connected to this
+    /// connector.
+    ///
+    /// Returns [`None`] if the driver or connector does not expose the
+    /// property.
+    pub fn content_type<D>(&self, card: &D) -> Result<Option<ContentType>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::get_enum_property(card, self.handle)
+    }
+
+    /// Returns the kind of signal currently carried by this connector, e.g.
+    /// DVI-D or DVI-A on a DVI-I connector.
+    ///
+    /// Returns [`None`] if the driver or connector does not expose the
+    /// property.
+    pub fn subconnector<D>(&self, card: &D) -> Result<Option<Subconnector>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::get_enum_property(card, self.handle)
+    }
+
+    /// Returns the subconnector selected for this connector.
+    ///
+    /// Returns [`None`] if the driver or connector does not expose the
+    /// property.
+    pub fn selected_subconnector<D>(
+        &self,
+        card: &D,
+    ) -> Result<Option<SelectSubconnector>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::get_enum_property(card, self.handle)
+    }
+
+    /// Returns the link status of this connector.
+    ///
+    /// Connectors without a link status property always report
+    /// [`LinkStatus::Good`].
+    pub fn link_status<D>(&self, card: &D) -> Result<LinkStatus, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        Ok(control::get_enum_property(card, self.handle)?.unwrap_or(LinkStatus::Good))
+    }
+
+    /// Returns the RGB quantization range used for this connector.
+    ///
+    /// Returns [`None`] if the driver or connector does not expose the
+    /// property.
+    pub fn broadcast_rgb<D>(&self, card: &D) -> Result<Option<BroadcastRgb>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::get_enum_property(card, self.handle)
+    }
+
+    /// Returns the colorspace signalled to the display connected to this
+    /// connector.
+    ///
+    /// Returns [`None`] if the driver or connector does not expose the
+    /// property.
+    pub fn colorspace<D>(&self, card: &D) -> Result<Option<Colorspace>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::get_enum_property(card, self.handle)
+    }
+
+    /// Returns the current value of the `max bpc` property of this connector.
+    ///
+    /// This is only an upper bound, the driver can fall back to a lower bit
+    /// depth, e.g. if the link bandwidth does not suffice. No standard
+    /// property reports the bit depth actually negotiated, some drivers only
+    /// expose it through debugfs. Returns [`None`] if the driver or connector
+    /// does not expose the property.
+    pub fn max_bpc<D>(&self, card: &D) -> Result<Option<u64>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        Ok(
+            control::find_property(card, self.handle, MAX_BPC_PROPERTY_NAMES)?
+                .map(|(_, value)| value),
+        )
+    }
+
+    /// Returns the range of values supported by the `max bpc` property of this
+    /// connector, which limits the bits per color component sent to the
+    /// display.
+    ///
+    /// Returns [`None`] if the driver or connector does not expose the
+    /// property.
+    pub fn max_bpc_range<D>(&self, card: &D) -> Result<Option<(u64, u64)>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let range = control::find_property(card, self.handle, MAX_BPC_PROPERTY_NAMES)?.and_then(
+            |(info, _)| match info.value_type() {
+                control::property::ValueType::UnsignedRange(min, max) => Some((min, max)),
+                _ => None,
+            },
+        );
+
+        Ok(range)
+    }
+
+    /// Returns the underscan mode of this connector.
+    ///
+    /// Returns [`None`] if the driver or connector does not expose the
+    /// property.
+    pub fn underscan<D>(&self, card: &D) -> Result<Option<Underscan>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        control::get_enum_property(card, self.handle)
+    }
+
+    /// Returns how the panel of this connector is mounted in its housing.
+    ///
+    /// Returns [`PanelOrientation::Normal`] if the driver or connector does
+    /// not expose the property, e.g. for external displays.
+    pub fn panel_orientation<D>(&self, card: &D) -> Result<PanelOrientation, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        Ok(control::get_enum_property(card, self.handle)?.unwrap_or(PanelOrientation::Normal))
+    }
+
+    /// Returns the horizontal and vertical underscan borders of this connector
+    /// in pixels.
+    ///
+    /// Each border is [`None`] if the driver or connector does not expose it.
+    pub fn underscan_borders<D>(&self, card: &D) -> Result<(Option<u64>, Option<u64>), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let hborder = control::find_property(card, self.handle, &[UNDERSCAN_HBORDER_PROPERTY])?
+            .map(|(_, value)| value);
+        let vborder = control::find_property(card, self.handle, &[UNDERSCAN_VBORDER_PROPERTY])?
+            .map(|(_, value)| value);
+
+        Ok((hborder, vborder))
+    }
+
+    /// Returns whether the display connected to this connector is not meant
+    /// to show a desktop, e.g. a VR headset.
+    ///
+    /// Compositors should not use such connectors as regular outputs, they
+    /// are usually leased to a dedicated client instead. Connectors without
+    /// the `non-desktop` property return `false`.
+    pub fn is_non_desktop<D>(&self, card: &D) -> Result<bool, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let non_desktop = control::find_property(card, self.handle, &["non-desktop"])?
+            .map_or(false, |(_, value)| value != 0);
+
+        Ok(non_desktop)
+    }
+
+    /// Returns the position at which the host suggests placing the output of
+    /// this connector, as `(x, y)` in pixels.
+    ///
+    /// Virtual drivers, e.g. virtio-gpu or vmwgfx, report the placement of
+    /// the host windows through the `suggested X` and `suggested Y`
+    /// properties, so that guests can arrange their outputs accordingly.
+    /// Returns [`None`] if the connector does not expose them, as on physical
+    /// hardware.
+    pub fn suggested_position<D>(&self, card: &D) -> Result<Option<(u32, u32)>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let x = control::find_property(card, self.handle, &["suggested X"])?;
+        let y = control::find_property(card, self.handle, &["suggested Y"])?;
+
+        Ok(match (x, y) {
+            (Some((_, x)), Some((_, y))) => Some((x as u32, y as u32)),
+            _ => None,
+        })
+    }
+
+    /// Returns the identification of the display connected to this
+    /// connector, read from its EDID.
+    ///
+    /// Returns [`None`] if the connector has no EDID, e.g. because no display
+    /// is connected, and [`SystemError::InvalidArgument`] if the EDID is
+    /// malformed.
+    pub fn display_info<D>(&self, card: &D) -> Result<Option<DisplayInfo>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let blob = control::find_property(card, self.handle, &["EDID"])?
+            .and_then(|(_, blob)| control::from_u32(blob as u32));
+        let edid = match blob {
+            Some(blob) => card.get_property_blob(blob)?,
+            None => return Ok(None),
+        };
+        DisplayInfo::parse(&edid)
+            .map(Some)
+            .ok_or(SystemError::InvalidArgument)
+    }
+
+    /// Returns the position of this connector within a tiled display, read
+    /// from its `TILE` property.
+    ///
+    /// Returns [`None`] if the connected display is not tiled, and
+    /// [`SystemError::InvalidArgument`] if the property is malformed. See
+    /// [`control::Device::tiled_display_groups`] to combine the tiles.
+    pub fn tile<D>(&self, card: &D) -> Result<Option<TileInfo>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let blob = control::find_property(card, self.handle, &["TILE"])?
+            .and_then(|(_, blob)| control::from_u32(blob as u32));
+        let tile = match blob {
+            Some(blob) => card.get_property_blob(blob)?,
+            None => return Ok(None),
+        };
+        TileInfo::parse(&tile)
+            .map(Some)
+            .ok_or(SystemError::InvalidArgument)
+    }
+
+    /// Returns the analog TV properties of this connector.
+    ///
+    /// Properties the connector does not expose are left unset, so connectors
+    /// without a TV output return empty [`TvProperties`].
+    pub fn tv_properties<D>(&self, card: &D) -> Result<TvProperties, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let mut props = TvProperties::new();
+
+        for (id, value) in card.get_properties(self.handle)? {
+            let info = card.get_property(id)?;
+            if is_tv_mode_property(&info) {
+                props.mode = control::enum_value_name(&info, value)
+                    .map(|name| name.to_string_lossy().into_owned());
+            } else if let Some(prop) = TvProperty::from_name(info.name()) {
+                props.set(prop, value);
+            }
+        }
+
+        Ok(props)
+    }
+}
+
+/// Names of the property limiting the bits per color component of a connector
+pub(crate) const MAX_BPC_PROPERTY_NAMES: &[&str] = &["max bpc"];
+
+/// Name of the property setting the horizontal underscan border of a connector
+pub(crate) const UNDERSCAN_HBORDER_PROPERTY: &str = "underscan hborder";
+
+/// Name of the property setting the vertical underscan border of a connector
+pub(crate) const UNDERSCAN_VBORDER_PROPERTY: &str = "underscan vborder";
+
+/// Names of the property selecting the TV standard of a connector
+const TV_MODE_PROPERTY_NAMES: &[&str] = &["TV mode", "mode"];
+
+pub(crate) fn is_tv_mode_property(info: &control::property::Info) -> bool {
+    TV_MODE_PROPERTY_NAMES
+        .iter()
+        .any(|known| control::name_matches(info.name(), known))
+}
+
+/// A range property of the analog TV output of a connector.
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum TvProperty {
+    LeftMargin,
+    RightMargin,
+    TopMargin,
+    BottomMargin,
+    Brightness,
+    Contrast,
+    Saturation,
+    Hue,
+    FlickerReduction,
+    Overscan,
+}
+
+impl TvProperty {
+    const ALL: [TvProperty; 10] = [
+        TvProperty::LeftMargin,
+        TvProperty::RightMargin,
+        TvProperty::TopMargin,
+        TvProperty::BottomMargin,
+        TvProperty::Brightness,
+        TvProperty::Contrast,
+        TvProperty::Saturation,
+        TvProperty::Hue,
+        TvProperty::FlickerReduction,
+        TvProperty::Overscan,
+    ];
+
+    /// Get the name of this property as used by the kernel
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TvProperty::LeftMargin => "left margin",
+            TvProperty::RightMargin => "right margin",
+            TvProperty::TopMargin => "top margin",
+            TvProperty::BottomMargin => "bottom margin",
+            TvProperty::Brightness => "brightness",
+            TvProperty::Contrast => "contrast",
+            TvProperty::Saturation => "saturation",
+            TvProperty::Hue => "hue",
+            TvProperty::FlickerReduction => "flicker reduction",
+            TvProperty::Overscan => "overscan",
+        }
+    }
+
+    pub(crate) fn from_name(name: &std::ffi::CStr) -> Option<Self> {
+        TvProperty::ALL
+            .iter()
+            .copied()
+            .find(|prop| control::name_matches(name, prop.as_str()))
+    }
+}
+
+/// Identification of a display, read from its EDID
+///
+/// Returned by [`Info::display_info`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct DisplayInfo {
+    pub(crate) manufacturer: String,
+    pub(crate) model: String,
+    pub(crate) serial: Option<String>,
+    pub(crate) name: Option<String>,
+}
+
+impl DisplayInfo {
+    /// Parses the base block of an EDID, returning [`None`] if it is
+    /// malformed.
+    pub fn parse(edid: &[u8]) -> Option<DisplayInfo> {
+        const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+        if edid.len() < 128 || edid[..8] != HEADER {
+            return None;
+        }
+
+        // Three letters of five bits each, starting at 1 for 'A'
+        let id = u16::from_be_bytes([edid[8], edid[9]]);
+        let manufacturer = [10, 5, 0]
+            .iter()
+            .map(|&shift| match (id >> shift) & 0x1f {
+                letter @ 1..=26 => char::from(b'A' + letter as u8 - 1),
+                _ => '?',
+            })
+            .collect();
+        let model = format!("{:04X}", u16::from_le_bytes([edid[10], edid[11]]));
+        let serial_number = u32::from_le_bytes([edid[12], edid[13], edid[14], edid[15]]);
+
+        let mut name = None;
+        let mut serial = None;
+        for descriptor in edid[54..126].chunks_exact(18) {
+            // Display descriptors start with a zero pixel clock
+            if descriptor[..3] != [0, 0, 0] {
+                continue;
+            }
+            // Text is terminated by a newline and padded with spaces
+            let text = &descriptor[5..];
+            let text = &text[..text.iter().position(|&b| b == b'\n').unwrap_or(text.len())];
+            let text = String::from_utf8_lossy(text).trim_end().to_string();
+            match descriptor[3] {
+                0xfc => name = Some(text),
+                0xff => serial = Some(text),
+                _ => {}
+            }
+        }
+
+        Some(DisplayInfo {
+            manufacturer,
+            model,
+            serial: serial.or_else(|| match serial_number {
+                0 => None,
+                serial_number => Some(serial_number.to_string()),
+            }),
+            name,
+        })
+    }
+
+    /// Returns the three letter PNP id of the manufacturer, e.g. `DEL`.
+    pub fn manufacturer(&self) -> &str {
+        &self.manufacturer
+    }
+
+    /// Returns the product code of the display as four hexadecimal digits.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Returns the serial number of the display, taken from the serial
+    /// number descriptor, or the numeric serial number if there is none.
+    pub fn serial(&self) -> Option<&str> {
+        self.serial.as_deref()
+    }
+
+    /// Returns the name of the display, e.g. `DELL U2720Q`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+/// The position of a connector within a tiled display
+///
+/// Returned by [`Info::tile`]. Large displays, e.g. some 5K and 8K monitors,
+/// are driven through several connectors, each showing one tile.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct TileInfo {
+    pub(crate) group_id: u32,
+    pub(crate) single_monitor: bool,
+    pub(crate) num_tiles: (u32, u32),
+    pub(crate) location: (u32, u32),
+    pub(crate) tile_size: (u32, u32),
+}
+
+impl TileInfo {
+    /// Parses the contents of a `TILE` blob, returning [`None`] if it is
+    /// malformed.
+    ///
+    /// The kernel formats the blob as eight colon separated numbers: group
+    /// id, flags, horizontal and vertical number of tiles, horizontal and
+    /// vertical location of this tile, and its width and height.
+    ///
+    /// ```
+    /// use drm::control::connector::TileInfo;
+    ///
+    /// // The right half of a 5K monitor
+    /// let tile = TileInfo::parse(b"3:1:2:1:1:0:2560:2880\0").unwrap();
+    /// assert_eq!(tile.group_id(), 3);
+    /// assert_eq!(tile.num_tiles(), (2, 1));
+    /// assert_eq!(tile.location(), (1, 0));
+    /// assert_eq!(tile.tile_size(), (2560, 2880));
+    /// ```
+    pub fn parse(blob: &[u8]) -> Option<TileInfo> {
+        let text = std::str::from_utf8(blob).ok()?.trim_end_matches('\0');
+        let mut fields = text
+            .split(':')
+            .map(|field| field.trim().parse::<u32>().ok());
+        let mut next = || fields.next().flatten();
+
+        let tile = TileInfo {
+            group_id: next()?,
+            single_monitor: next()? & 1 != 0,
+            num_tiles: (next()?, next()?),
+            location: (next()?, next()?),
+            tile_size: (next()?, next()?),
+        };
+        if fields.next().is_some()
+            || tile.location.0 >= tile.num_tiles.0
+            || tile.location.1 >= tile.num_tiles.1
+        {
+            return None;
+        }
+
+        Some(tile)
+    }
+
+    /// Returns the id of the tile group, shared by all tiles of a display.
+    pub fn group_id(&self) -> u32 {
+        self.group_id
+    }
+
+    /// Returns whether the tiles are housed in a single physical monitor.
+    pub fn is_single_monitor(&self) -> bool {
+        self.single_monitor
+    }
+
+    /// Returns the number of tiles of the display, horizontally and
+    /// vertically.
+    pub fn num_tiles(&self) -> (u32, u32) {
+        self.num_tiles
+    }
+
+    /// Returns the column and row of this tile, starting at `(0, 0)` in the
+    /// top left corner.
+    pub fn location(&self) -> (u32, u32) {
+        self.location
+    }
+
+    /// Returns the size of this tile in pixels.
+    pub fn tile_size(&self) -> (u32, u32) {
+        self.tile_size
+    }
+}
+
+/// The connectors driving the tiles of one tiled display
+///
+/// Returned by [`control::Device::tiled_display_groups`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct TiledGroup {
+    pub(crate) group_id: u32,
+    pub(crate) tiles: Vec<(Handle, TileInfo)>,
+}
+
+impl TiledGroup {
+    /// Returns the id of the tile group.
+    pub fn group_id(&self) -> u32 {
+        self.group_id
+    }
+
+    /// Returns the connectors and their tiles, ordered row by row from the
+    /// top left corner.
+    pub fn tiles(&self) -> &[(Handle, TileInfo)] {
+        &self.tiles
+    }
+
+    /// Returns whether a connector was found for every tile of the display.
+    pub fn is_complete(&self) -> bool {
+        let (columns, rows) = self.tiles[0].1.num_tiles;
+        self.tiles.len() as u64 == u64::from(columns) * u64::from(rows)
+    }
+
+    /// Returns the offset of a tile within the combined display in pixels.
+    ///
+    /// Tiles in the same column share their width and tiles in the same row
+    /// their height, so the offset sums the sizes of the tiles to the left
+    /// and above.
+    pub fn tile_offset(&self, tile: &TileInfo) -> (u32, u32) {
+        let (column, row) = tile.location;
+        (
+            self.column_widths().take(column as usize).sum(),
+            self.row_heights().take(row as usize).sum(),
+        )
+    }
+
+    /// Returns the size of the combined display in pixels.
+    ///
+    /// Tiles missing from an incomplete group are not accounted for.
+    pub fn size(&self) -> (u32, u32) {
+        (self.column_widths().sum(), self.row_heights().sum())
+    }
+
+    fn column_widths(&self) -> impl Iterator<Item = u32> + '_ {
+        let columns = self.tiles[0].1.num_tiles.0;
+        (0..columns).map(move |column| {
+            self.tiles
+                .iter()
+                .find(|(_, tile)| tile.location.0 == column)
+                .map_or(0, |(_, tile)| tile.tile_size.0)
+        })
+    }
+
+    fn row_heights(&self) -> impl Iterator<Item = u32> + '_ {
+        let rows = self.tiles[0].1.num_tiles.1;
+        (0..rows).map(move |row| {
+            self.tiles
+                .iter()
+                .find(|(_, tile)| tile.location.1 == row)
+                .map_or(0, |(_, tile)| tile.tile_size.1)
+        })
+    }
+}
+
+/// The analog TV properties of a connector.
+///
+/// Returned by [`Info::tv_properties`], and used to change the properties
+/// with [`control::Device::set_tv_properties`], which only touches the
+/// properties that are set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TvProperties {
+    pub(crate) mode: Option<String>,
+    pub(crate) values: Vec<(TvProperty, u64)>,
+}
+
+impl TvProperties {
+    /// Creates an empty set of properties.
+    pub fn new() -> TvProperties {
+        Self::default()
+    }
+
+    /// Returns the name of the TV standard, e.g. `NTSC` or `PAL`.
+    pub fn mode(&self) -> Option<&str> {
+        self.mode.as_deref()
+    }
+
+    /// Sets the name of the TV standard.
+    pub fn set_mode(&mut self, mode: &str) -> &mut Self {
+        self.mode = Some(mode.to_owned());
+        self
+    }
+
+    /// Returns the value of a range property.
+    pub fn get(&self, prop: TvProperty) -> Option<u64> {
+        self.values
+            .iter()
+            .find(|(known, _)| *known == prop)
+            .map(|&(_, value)| value)
+    }
+
+    /// Sets the value of a range property.
+    pub fn set(&mut self, prop: TvProperty, value: u64) -> &mut Self {
+        match self.values.iter_mut().find(|(known, _)| *known == prop) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((prop, value)),
+        }
+        self
+    }
 }
 
 /// A physical interface type.
@@ -220,6 +951,253 @@ impl From<Interface> for u32 {
     }
 }
 
+property_enum! {
+    /// How a connector displays modes that do not match the native resolution
+    /// of the panel.
+    pub enum ScalingMode ["scaling mode"] {
+        /// The mode is displayed without any scaling
+        None => "None",
+        /// The mode is stretched to fill the panel
+        Full => "Full",
+        /// The mode is centered on the panel without scaling
+        Center => "Center",
+        /// The mode is scaled up as far as possible, keeping its aspect ratio
+        FullAspect => "Full aspect",
+    }
+}
+
+property_enum! {
+    /// Content protection (HDCP) state of a connector.
+    ///
+    /// Clients may only request [`ContentProtection::Desired`] or
+    /// [`ContentProtection::Undesired`], [`ContentProtection::Enabled`] is set
+    /// by the kernel once the link is protected.
+    pub enum ContentProtection ["Content Protection"] {
+        /// Content protection is not requested
+        Undesired => "Undesired",
+        /// Content protection is requested, but not yet established
+        Desired => "Desired",
+        /// Content protection is established on the link
+        Enabled => "Enabled",
+    }
+}
+
+property_enum! {
+    /// The type of content to be protected by HDCP.
+    pub enum HdcpContentType ["HDCP Content Type"] {
+        /// Content can be transmitted with any HDCP version
+        Type0 => "HDCP Type0",
+        /// Content must only be transmitted with HDCP 2.2 or later
+        Type1 => "HDCP Type1",
+    }
+}
+
+property_enum! {
+    /// The type of content signalled to an HDMI display, e.g. to let a TV
+    /// switch to a low latency mode for games.
+    pub enum ContentType ["content type"] {
+        /// No content type is signalled
+        NoData => "No Data",
+        /// Graphics, e.g. a desktop
+        Graphics => "Graphics",
+        /// Still pictures
+        Photo => "Photo",
+        /// Movies
+        Cinema => "Cinema",
+        /// Games, which benefit from low latency
+        Game => "Game",
+    }
+}
+
+property_enum! {
+    /// The kind of signal carried by a connector supporting several, e.g. a
+    /// DVI-I, TV or DisplayPort connector.
+    pub enum Subconnector ["subconnector"] {
+        /// The subconnector is not known
+        Unknown => "Unknown",
+        /// Digital DVI
+        DviD => "DVI-D",
+        /// Analog DVI
+        DviA => "DVI-A",
+        /// Composite TV output
+        Composite => "Composite",
+        /// S-Video TV output
+        SVideo => "SVIDEO",
+        /// Component TV output
+        Component => "Component",
+        /// SCART TV output
+        Scart => "SCART",
+        /// VGA through a DisplayPort adapter
+        Vga => "VGA",
+        /// HDMI through a DisplayPort adapter
+        Hdmi => "HDMI",
+        /// DisplayPort through a DisplayPort adapter
+        DisplayPort => "DP",
+        /// Wireless DisplayPort
+        Wireless => "Wireless",
+        /// Native DisplayPort
+        Native => "Native",
+    }
+}
+
+property_enum! {
+    /// The subconnector a DVI-I or TV connector is forced to use.
+    pub enum SelectSubconnector ["select subconnector"] {
+        /// The driver picks the subconnector
+        Automatic => "Automatic",
+        /// Digital DVI
+        DviD => "DVI-D",
+        /// Analog DVI
+        DviA => "DVI-A",
+        /// Composite TV output
+        Composite => "Composite",
+        /// S-Video TV output
+        SVideo => "SVIDEO",
+        /// Component TV output
+        Component => "Component",
+        /// SCART TV output
+        Scart => "SCART",
+    }
+}
+
+property_enum! {
+    /// Status of the link between a connector and the display.
+    ///
+    /// The kernel sets the link status to [`LinkStatus::Bad`] if link training
+    /// failed, see [`control::Device::reset_link_status`].
+    pub enum LinkStatus ["link-status"] {
+        /// The link is working
+        Good => "Good",
+        /// Link training failed, a modeset is required to retrain the link
+        Bad => "Bad",
+    }
+}
+
+property_enum! {
+    /// RGB quantization range sent to the display.
+    pub enum BroadcastRgb ["Broadcast RGB"] {
+        /// The range is chosen based on the mode
+        Automatic => "Automatic",
+        /// Full range, 0-255
+        Full => "Full",
+        /// Limited range, 16-235
+        Limited => "Limited 16:235",
+    }
+}
+
+property_enum! {
+    /// Colorspace signalled to the display.
+    pub enum Colorspace ["Colorspace"] {
+        /// The default colorspace of the display
+        Default => "Default",
+        /// ITU-R BT.601 YCbCr (SMPTE 170M)
+        Smpte170mYcc => "SMPTE_170M_YCC",
+        /// ITU-R BT.709 YCbCr
+        Bt709Ycc => "BT709_YCC",
+        /// xvYCC based on BT.601
+        Xvycc601 => "XVYCC_601",
+        /// xvYCC based on BT.709
+        Xvycc709 => "XVYCC_709",
+        /// sYCC based on BT.601
+        Sycc601 => "SYCC_601",
+        /// opYCC based on BT.601
+        Opycc601 => "opYCC_601",
+        /// opRGB
+        Oprgb => "opRGB",
+        /// ITU-R BT.2020 Y'cC'bcC'rc (constant luminance)
+        Bt2020Cycc => "BT2020_CYCC",
+        /// ITU-R BT.2020 R'G'B'
+        Bt2020Rgb => "BT2020_RGB",
+        /// ITU-R BT.2020 Y'C'bC'r
+        Bt2020Ycc => "BT2020_YCC",
+        /// DCI-P3 with a D65 white point
+        DciP3RgbD65 => "DCI-P3_RGB_D65",
+        /// DCI-P3 with a theater white point
+        DciP3RgbTheater => "DCI-P3_RGB_Theater",
+        /// Wide gamut RGB with fixed point values
+        RgbWideFixed => "RGB_WIDE_FIXED",
+        /// Wide gamut RGB with floating point values
+        RgbWideFloat => "RGB_WIDE_FLOAT",
+        /// ITU-R BT.601 YCbCr
+        Bt601Ycc => "BT601_YCC",
+    }
+}
+
+property_enum! {
+    /// Whether the image is shrunk to compensate for the overscan of a TV.
+    ///
+    /// The size of the borders is set by the `underscan hborder` and
+    /// `underscan vborder` properties, see
+    /// [`control::Device::set_underscan`].
+    pub enum Underscan ["underscan"] {
+        /// The image fills the whole display
+        Off => "off",
+        /// The image is surrounded by the underscan borders
+        On => "on",
+        /// Underscan is enabled for HDMI displays with TV modes
+        Automatic => "auto",
+    }
+}
+
+property_enum! {
+    /// The orientation in which the panel of a connector is mounted.
+    ///
+    /// Set by the kernel for built-in panels of e.g. tablets, whose native
+    /// orientation does not match the orientation of the device.
+    pub enum PanelOrientation ["panel orientation"] {
+        /// The top of the panel matches the top of the device
+        Normal => "Normal",
+        /// The panel is mounted upside down
+        UpsideDown => "Upside Down",
+        /// The left side of the panel is at the top of the device
+        LeftSideUp => "Left Side Up",
+        /// The right side of the panel is at the top of the device
+        RightSideUp => "Right Side Up",
+    }
+}
+
+impl PanelOrientation {
+    /// Returns the rotation to apply to the planes of the connector's CRTC, so
+    /// that the image appears upright on the device.
+    ///
+    /// See [`AtomicModeReq::set_rotation`](control::atomic::AtomicModeReq::set_rotation).
+    pub fn rotation(&self) -> control::plane::Rotation {
+        use control::plane::Rotation;
+
+        match self {
+            PanelOrientation::Normal => Rotation::ROTATE_0,
+            PanelOrientation::UpsideDown => Rotation::ROTATE_180,
+            PanelOrientation::LeftSideUp => Rotation::ROTATE_90,
+            PanelOrientation::RightSideUp => Rotation::ROTATE_270,
+        }
+    }
+}
+
+/// A typed value of a connector property
+///
+/// Several settings can be batched into one atomic commit with
+/// [`AtomicModeReq::set_connector_property`](control::atomic::AtomicModeReq::set_connector_property),
+/// so that they are applied together.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum Setting {
+    /// The `scaling mode` property
+    ScalingMode(ScalingMode),
+    /// The `Broadcast RGB` property
+    BroadcastRgb(BroadcastRgb),
+    /// The `Colorspace` property
+    Colorspace(Colorspace),
+    /// The `HDCP Content Type` property
+    HdcpContentType(HdcpContentType),
+    /// The `content type` property
+    ContentType(ContentType),
+    /// The `max bpc` property, clamped to the range supported by the driver
+    MaxBpc(u64),
+    /// The `underscan` property
+    Underscan(Underscan),
+    /// The `select subconnector` property
+    SelectSubconnector(SelectSubconnector),
+}
+
 /// The state of a connector.
 #[allow(missing_docs)]
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -252,3 +1230,83 @@ impl From<State> for u32 {
         }
     }
 }
+
+/// A change of a connector reported by [`HotplugTracker::reconcile`]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum ConnectorChange {
+    /// A display was connected, or a connected connector appeared, e.g. on a
+    /// DisplayPort MST hub
+    Connected(Handle),
+    /// A display was disconnected, or a connected connector disappeared
+    Disconnected(Handle),
+    /// The modes of a connected display changed, e.g. after it was replaced
+    ModesChanged(Handle),
+}
+
+/// Tracks the connection state of connectors to find out what changed after a
+/// hotplug event
+///
+/// The tracker starts out empty, so the first call to
+/// [`HotplugTracker::reconcile`] reports every connected connector.
+#[derive(Debug, Clone, Default)]
+pub struct HotplugTracker {
+    connectors: HashMap<Handle, (State, Vec<control::Mode>)>,
+}
+
+impl HotplugTracker {
+    /// Creates a tracker that does not know of any connector yet.
+    pub fn new() -> HotplugTracker {
+        Self::default()
+    }
+
+    /// Compares the current state of the connectors with the state seen by
+    /// the previous call, and returns the changes.
+    ///
+    /// Connectors are read without forcing a probe, so this is cheap enough to
+    /// call on every hotplug event. The kernel has already probed the
+    /// connectors when it sends one.
+    pub fn reconcile<D>(&mut self, card: &D) -> Result<Vec<ConnectorChange>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let mut current = Vec::new();
+        for &handle in card.resource_handles()?.connectors() {
+            match card.get_connector(handle, false) {
+                Ok(info) => current.push((handle, info.connection, info.modes)),
+                // MST connectors can be removed while the list is read
+                Err(SystemError::Unknown {
+                    errno: nix::errno::Errno::ENOENT,
+                }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let mut changes = Vec::new();
+        for (handle, state, modes) in &current {
+            let connected = *state == State::Connected;
+            match self.connectors.get(handle) {
+                Some((State::Connected, _)) if !connected => {
+                    changes.push(ConnectorChange::Disconnected(*handle))
+                }
+                Some((State::Connected, old_modes)) if old_modes != modes => {
+                    changes.push(ConnectorChange::ModesChanged(*handle))
+                }
+                Some((State::Connected, _)) => {}
+                _ if connected => changes.push(ConnectorChange::Connected(*handle)),
+                _ => {}
+            }
+        }
+        for (&handle, (state, _)) in &self.connectors {
+            let removed = !current.iter().any(|(known, _, _)| *known == handle);
+            if *state == State::Connected && removed {
+                changes.push(ConnectorChange::Disconnected(handle));
+            }
+        }
+
+        self.connectors = current
+            .into_iter()
+            .map(|(handle, state, modes)| (handle, (state, modes)))
+            .collect();
+        Ok(changes)
+    }
+}
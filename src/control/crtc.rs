@@ -14,6 +14,8 @@
 
 use control;
 use drm_ffi as ffi;
+use drm_ffi::result::SystemError;
+use std::collections::HashMap;
 
 /// A handle to a specific CRTC
 #[repr(transparent)]
@@ -68,6 +70,22 @@ impl Info {
         self.handle
     }
 
+    /// Returns the properties of this CRTC along with their decoded values,
+    /// keyed by property name.
+    ///
+    /// Use [`control::Device::get_named_properties`] with a shared
+    /// [`control::property::InfoCache`] when reading the properties of
+    /// several objects.
+    pub fn properties<D>(
+        &self,
+        card: &D,
+    ) -> Result<HashMap<String, control::dump::ValueDump>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        card.get_named_properties(self.handle, &control::property::InfoCache::new(card))
+    }
+
     /// Returns the position of the CRTC.
     pub fn position(&self) -> (u32, u32) {
         self.position
@@ -87,4 +105,48 @@ impl Info {
     pub fn gamma_length(&self) -> u32 {
         self.gamma_length
     }
+
+    /// Returns the color shown where no plane covers the CRTC, in the
+    /// ARGB64 layout described in [`argb64`].
+    ///
+    /// Returns [`None`] if the driver does not expose a background color
+    /// property. See
+    /// [`AtomicModeReq::set_background_color`](control::atomic::AtomicModeReq::set_background_color)
+    /// to change it.
+    pub fn background_color<D>(&self, card: &D) -> Result<Option<u64>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        Ok(control::find_property(card, self.handle, BACKGROUND_COLOR)?.map(|(_, value)| value))
+    }
+}
+
+/// Names under which drivers expose the background color of a CRTC
+pub(crate) const BACKGROUND_COLOR: &[&str] = &["BACKGROUND_COLOR", "BACKGROUND"];
+
+/// Encodes a color in the ARGB64 layout of the CRTC background color, with
+/// 16 bits per channel and alpha in the most significant bits.
+///
+/// ```
+/// use drm::control::crtc;
+///
+/// assert_eq!(crtc::argb64(0xffff, 0, 0x8000, 0xffff), 0xffff_ffff_0000_8000);
+/// ```
+pub const fn argb64(red: u16, green: u16, blue: u16, alpha: u16) -> u64 {
+    (alpha as u64) << 48 | (red as u64) << 32 | (green as u64) << 16 | blue as u64
+}
+
+/// The legacy gamma ramp of a CRTC
+///
+/// Read with [`control::Device::get_gamma_ramp`], e.g. to restore it after
+/// changing it with [`control::Device::set_gamma_ramp`]. All channels have
+/// [`Info::gamma_length`] entries.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct GammaRamp {
+    /// The red channel
+    pub red: Vec<u16>,
+    /// The green channel
+    pub green: Vec<u16>,
+    /// The blue channel
+    pub blue: Vec<u16>,
 }
@@ -5,6 +5,9 @@
 //!
 
 use buffer;
+use buffer::format::FormatInfo;
+use control::{self, crtc, framebuffer, Event, PageFlipFlags};
+use drm_ffi::result::SystemError;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// Slow, but generic [`buffer::Buffer`] implementation
@@ -13,6 +16,7 @@ pub struct DumbBuffer {
     pub(crate) length: usize,
     pub(crate) format: buffer::DrmFourcc,
     pub(crate) pitch: u32,
+    pub(crate) bpp: u32,
     pub(crate) handle: buffer::Handle,
 }
 
@@ -20,6 +24,53 @@ pub struct DumbBuffer {
 pub struct DumbMapping<'a> {
     pub(crate) _phantom: core::marker::PhantomData<&'a ()>,
     pub(crate) map: &'a mut [u8],
+    pub(crate) size: (u32, u32),
+    pub(crate) pitch: u32,
+    pub(crate) bpp: u32,
+}
+
+impl<'a> DumbMapping<'a> {
+    /// Sets every pixel of the buffer to `pixel`, e.g. `0xff0000` for red in
+    /// [`buffer::DrmFourcc::Xrgb8888`].
+    ///
+    /// Only the visible pixels of each row are written, the padding between
+    /// the end of a row and the start of the next is left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer was not created with 32 bits per pixel.
+    pub fn fill_u32(&mut self, pixel: u32) {
+        assert_eq!(self.bpp, 32, "buffer does not have 32 bits per pixel");
+
+        let pixel = pixel.to_le_bytes();
+        for row in self.rows_mut() {
+            for dst in row.chunks_exact_mut(4) {
+                dst.copy_from_slice(&pixel);
+            }
+        }
+    }
+
+    /// Sets the visible pixels of the buffer to zero, which is black for
+    /// RGB formats.
+    pub fn clear(&mut self) {
+        for row in self.rows_mut() {
+            row.fill(0);
+        }
+    }
+
+    /// Returns the visible part of each row of the buffer.
+    fn rows_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        let (width, height) = self.size;
+        let row_len = (width as usize * self.bpp as usize + 7) / 8;
+
+        self.map
+            .chunks_mut(self.pitch as usize)
+            .take(height as usize)
+            .map(move |row| {
+                let len = row_len.min(row.len());
+                &mut row[..len]
+            })
+    }
 }
 
 impl<'a> AsMut<[u8]> for DumbMapping<'a> {
@@ -52,3 +103,146 @@ impl buffer::Buffer for DumbBuffer {
         self.handle
     }
 }
+
+/// Pair of [`DumbBuffer`]s with framebuffers, used to draw on the CPU while the
+/// other buffer is being scanned out.
+///
+/// Draw into the back buffer using [`DoubleBuffer::back_buffer_mut`] and
+/// present it with [`DoubleBuffer::flip`]. The CRTC must already be scanning
+/// out one of the framebuffers, e.g. by passing
+/// [`DoubleBuffer::front_framebuffer`] to [`control::Device::set_crtc`].
+///
+/// Both framebuffers and buffers are destroyed on drop.
+pub struct DoubleBuffer<'a, D: control::Device> {
+    card: &'a D,
+    buffers: [DumbBuffer; 2],
+    framebuffers: [framebuffer::Handle; 2],
+    back: usize,
+    pending_flip: Option<crtc::Handle>,
+}
+
+impl<'a, D: control::Device> DoubleBuffer<'a, D> {
+    /// Creates both buffers and framebuffers with the given size and format.
+    ///
+    /// The framebuffers are created with
+    /// [`control::Device::add_framebuffer_for`], so they keep the exact format.
+    /// Returns [`SystemError::InvalidArgument`] if the format is unknown to
+    /// [`FormatInfo`] or has more than one plane.
+    pub fn new(
+        card: &'a D,
+        size: (u32, u32),
+        format: buffer::DrmFourcc,
+    ) -> Result<Self, SystemError> {
+        let bpp = FormatInfo::new(format)
+            .ok_or(SystemError::InvalidArgument)?
+            .bpp();
+
+        let front = card.create_dumb_buffer(size, format, bpp)?;
+        let back = match card.create_dumb_buffer(size, format, bpp) {
+            Ok(back) => back,
+            Err(err) => {
+                let _ = card.destroy_dumb_buffer(front);
+                return Err(err);
+            }
+        };
+
+        let mut framebuffers = Vec::with_capacity(2);
+        for buffer in [&front, &back] {
+            match card.add_framebuffer_for(buffer) {
+                Ok(fb) => framebuffers.push(fb),
+                Err(err) => {
+                    for fb in framebuffers {
+                        let _ = card.destroy_framebuffer(fb);
+                    }
+                    let _ = card.destroy_dumb_buffer(front);
+                    let _ = card.destroy_dumb_buffer(back);
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(DoubleBuffer {
+            card,
+            buffers: [front, back],
+            framebuffers: [framebuffers[0], framebuffers[1]],
+            back: 1,
+            pending_flip: None,
+        })
+    }
+
+    /// Returns the framebuffer currently presented, or about to be presented
+    /// if a flip is pending.
+    pub fn front_framebuffer(&self) -> framebuffer::Handle {
+        self.framebuffers[1 - self.back]
+    }
+
+    /// Returns the framebuffer that will be presented by the next flip.
+    pub fn back_framebuffer(&self) -> framebuffer::Handle {
+        self.framebuffers[self.back]
+    }
+
+    /// Returns whether a flip has been queued, but not completed yet.
+    pub fn is_flip_pending(&self) -> bool {
+        self.pending_flip.is_some()
+    }
+
+    /// Maps the back buffer for drawing.
+    ///
+    /// If a flip is pending, this blocks until it completes, as the back
+    /// buffer is still being scanned out until then.
+    pub fn back_buffer_mut(&mut self) -> Result<DumbMapping<'_>, SystemError> {
+        self.wait_for_flip()?;
+        self.card.map_dumb_buffer(&mut self.buffers[self.back])
+    }
+
+    /// Queues a page flip of the given CRTC to the back buffer and swaps the
+    /// buffers.
+    ///
+    /// If a previous flip is still pending, this blocks until it completes.
+    pub fn flip(&mut self, crtc: crtc::Handle) -> Result<(), SystemError> {
+        self.wait_for_flip()?;
+        self.card
+            .page_flip(crtc, self.back_framebuffer(), PageFlipFlags::EVENT, None)?;
+        self.pending_flip = Some(crtc);
+        self.back = 1 - self.back;
+
+        Ok(())
+    }
+
+    /// Marks the pending flip as completed, if `event` belongs to it.
+    ///
+    /// Use this when running a custom event loop, so that
+    /// [`DoubleBuffer::flip`] and [`DoubleBuffer::back_buffer_mut`] do not need
+    /// to wait for the event themselves.
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::PageFlip(flip) = event {
+            if self.pending_flip == Some(flip.crtc) {
+                self.pending_flip = None;
+            }
+        }
+    }
+
+    /// Blocks until the pending flip, if any, completes.
+    ///
+    /// Events of the device not belonging to the flip are discarded. This also
+    /// blocks on devices opened with `O_NONBLOCK`, see
+    /// [`control::Device::wait_events`].
+    pub fn wait_for_flip(&mut self) -> Result<(), SystemError> {
+        while self.pending_flip.is_some() {
+            for event in self.card.wait_events(None)?.into_iter().flatten() {
+                self.handle_event(&event);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, D: control::Device> Drop for DoubleBuffer<'a, D> {
+    fn drop(&mut self) {
+        for (&fb, &buffer) in self.framebuffers.iter().zip(self.buffers.iter()) {
+            let _ = self.card.destroy_framebuffer(fb);
+            let _ = self.card.destroy_dumb_buffer(buffer);
+        }
+    }
+}
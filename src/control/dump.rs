@@ -0,0 +1,288 @@
+//! # Dump
+//!
+//! A snapshot of the mode setting state of a device.
+//!
+//! [`Device::dump_state`](super::Device::dump_state) captures the connectors,
+//! encoders, CRTCs, planes and framebuffers of a device along with the decoded
+//! values of all their properties. With the `serde` feature enabled, a
+//! [`CardDump`] can be serialized, e.g. to JSON, to compare the state of a
+//! display over time.
+
+use control::{self, property, Mode, ResourceHandle};
+use drm_ffi as ffi;
+use drm_ffi::result::SystemError;
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// The state of all mode setting objects of a device
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct CardDump {
+    pub(crate) connectors: Vec<ObjectDump>,
+    pub(crate) encoders: Vec<ObjectDump>,
+    pub(crate) crtcs: Vec<ObjectDump>,
+    pub(crate) planes: Vec<ObjectDump>,
+    pub(crate) framebuffers: Vec<ObjectDump>,
+}
+
+impl CardDump {
+    pub(crate) fn capture<D: control::Device + ?Sized>(card: &D) -> Result<Self, SystemError> {
+        let res = card.resource_handles()?;
+        let planes = card.plane_handles()?;
+        let cache = property::InfoCache::new(card);
+
+        Ok(CardDump {
+            connectors: dump_objects(&cache, res.connectors())?,
+            encoders: dump_objects(&cache, res.encoders())?,
+            crtcs: dump_objects(&cache, res.crtcs())?,
+            planes: dump_objects(&cache, &planes)?,
+            framebuffers: dump_objects(&cache, res.framebuffers())?,
+        })
+    }
+
+    /// Returns the state of all connectors.
+    pub fn connectors(&self) -> &[ObjectDump] {
+        &self.connectors
+    }
+
+    /// Returns the state of all encoders.
+    pub fn encoders(&self) -> &[ObjectDump] {
+        &self.encoders
+    }
+
+    /// Returns the state of all CRTCs.
+    pub fn crtcs(&self) -> &[ObjectDump] {
+        &self.crtcs
+    }
+
+    /// Returns the state of all planes.
+    pub fn planes(&self) -> &[ObjectDump] {
+        &self.planes
+    }
+
+    /// Returns the state of all framebuffers.
+    pub fn framebuffers(&self) -> &[ObjectDump] {
+        &self.framebuffers
+    }
+}
+
+/// The state of a single mode setting object
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ObjectDump {
+    pub(crate) id: u32,
+    pub(crate) properties: Vec<PropertyDump>,
+}
+
+impl ObjectDump {
+    /// Returns the raw id of the object.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the properties of the object.
+    pub fn properties(&self) -> &[PropertyDump] {
+        &self.properties
+    }
+}
+
+/// A property and its current value
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PropertyDump {
+    pub(crate) id: u32,
+    pub(crate) name: String,
+    pub(crate) value: ValueDump,
+}
+
+impl PropertyDump {
+    /// Returns the raw id of the property.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the name of the property.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the decoded value of the property.
+    pub fn value(&self) -> &ValueDump {
+        &self.value
+    }
+}
+
+/// The decoded value of a property
+///
+/// Owned counterpart of [`property::Value`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ValueDump {
+    /// Value of an unknown type
+    Unknown(u64),
+    /// Boolean value
+    Boolean(bool),
+    /// Unsigned range value
+    UnsignedRange(u64),
+    /// Signed range value
+    SignedRange(i64),
+    /// Enum value, along with its name if it is a valid value
+    Enum {
+        /// The raw value
+        value: u64,
+        /// The name of the value
+        name: Option<String>,
+    },
+    /// Bitmask value
+    Bitmask(u64),
+    /// Blob value, or [`None`] if no blob is set
+    Blob(Option<BlobDump>),
+    /// Raw id of the referenced object, or [`None`] if no object is set
+    Object(Option<u32>),
+}
+
+/// The contents of a blob
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum BlobDump {
+    /// A mode, e.g. the `MODE_ID` property of a CRTC
+    Mode(Mode),
+    /// Data without a known format
+    Raw(Vec<u8>),
+}
+
+/// Reads the properties of an object along with their decoded values, keyed
+/// by property name.
+pub(crate) fn named_properties<D, T>(
+    cache: &property::InfoCache<D>,
+    handle: T,
+) -> Result<HashMap<String, ValueDump>, SystemError>
+where
+    D: control::Device + ?Sized,
+    T: ResourceHandle,
+{
+    let card = cache.card();
+    card.get_properties(handle)?
+        .iter()
+        .map(|(&prop, &value)| {
+            let prop = dump_property(card, &cache.get(prop)?, value)?;
+            Ok((prop.name, prop.value))
+        })
+        .collect()
+}
+
+fn dump_objects<D, T>(
+    cache: &property::InfoCache<D>,
+    handles: &[T],
+) -> Result<Vec<ObjectDump>, SystemError>
+where
+    D: control::Device + ?Sized,
+    T: ResourceHandle,
+{
+    handles
+        .iter()
+        .map(|&handle| dump_object(cache, handle))
+        .collect()
+}
+
+fn dump_object<D, T>(cache: &property::InfoCache<D>, handle: T) -> Result<ObjectDump, SystemError>
+where
+    D: control::Device + ?Sized,
+    T: ResourceHandle,
+{
+    let card = cache.card();
+    let props = match card.get_properties(handle) {
+        Ok(props) => props,
+        // Encoders and framebuffers cannot have properties, which is reported
+        // as an invalid argument.
+        Err(SystemError::InvalidArgument) => {
+            return Ok(ObjectDump {
+                id: handle.into(),
+                properties: Vec::new(),
+            })
+        }
+        Err(err) => return Err(err),
+    };
+
+    let properties = props
+        .iter()
+        .map(|(&prop, &value)| dump_property(card, &cache.get(prop)?, value))
+        .collect::<Result<_, _>>()?;
+
+    Ok(ObjectDump {
+        id: handle.into(),
+        properties,
+    })
+}
+
+fn dump_property<D: control::Device + ?Sized>(
+    card: &D,
+    info: &property::Info,
+    raw: property::RawValue,
+) -> Result<PropertyDump, SystemError> {
+    let name = info.name().to_string_lossy().into_owned();
+    let value_type = info.value_type();
+
+    let value = match value_type.convert_value(raw) {
+        property::Value::Unknown(x) => ValueDump::Unknown(x),
+        property::Value::Boolean(x) => ValueDump::Boolean(x),
+        property::Value::UnsignedRange(x) => ValueDump::UnsignedRange(x),
+        property::Value::SignedRange(x) => ValueDump::SignedRange(x),
+        property::Value::Enum(x) => ValueDump::Enum {
+            value: raw,
+            name: x.map(|x| x.name().to_string_lossy().into_owned()),
+        },
+        property::Value::Bitmask(x) => ValueDump::Bitmask(x),
+        property::Value::Blob(None) => ValueDump::Blob(None),
+        property::Value::Blob(Some(x)) => ValueDump::Blob(Some(dump_blob(card, &name, x)?)),
+        property::Value::Object(x) => ValueDump::Object(x.map(u32::from)),
+        property::Value::CRTC(x) => ValueDump::Object(x.map(u32::from)),
+        property::Value::Connector(x) => ValueDump::Object(x.map(u32::from)),
+        property::Value::Encoder(x) => ValueDump::Object(x.map(u32::from)),
+        property::Value::Framebuffer(x) => ValueDump::Object(x.map(u32::from)),
+        property::Value::Plane(x) => ValueDump::Object(x.map(u32::from)),
+        property::Value::Property(x) => ValueDump::Object(x.map(u32::from)),
+    };
+
+    Ok(PropertyDump {
+        id: info.handle().into(),
+        name,
+        value,
+    })
+}
+
+fn dump_blob<D: control::Device + ?Sized>(
+    card: &D,
+    name: &str,
+    blob: control::blob::Handle,
+) -> Result<BlobDump, SystemError> {
+    let data = card.get_property_blob(blob)?;
+
+    if name == "MODE_ID" && data.len() == std::mem::size_of::<ffi::drm_mode_modeinfo>() {
+        let mode: ffi::drm_mode_modeinfo =
+            unsafe { std::ptr::read_unaligned(data.as_ptr() as *const _) };
+        return Ok(BlobDump::Mode(mode.into()));
+    }
+
+    Ok(BlobDump::Raw(data))
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Mode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Mode", 10)?;
+        state.serialize_field("name", &self.name().to_string_lossy())?;
+        state.serialize_field("clock", &self.clock())?;
+        state.serialize_field("size", &self.size())?;
+        state.serialize_field("hsync", &self.hsync())?;
+        state.serialize_field("vsync", &self.vsync())?;
+        state.serialize_field("hskew", &self.hskew())?;
+        state.serialize_field("vscan", &self.vscan())?;
+        state.serialize_field("vrefresh", &self.vrefresh())?;
+        state.serialize_field("mode_type", &self.mode_type().bits())?;
+        state.serialize_field("flags", &self.flags().bits())?;
+        state.end()
+    }
+}
@@ -59,7 +59,8 @@ impl Info {
         self.handle
     }
 
-    /// Returns the `Kind` of encoder this is.
+    /// Returns the `Kind` of encoder this is, decoded from the
+    /// `DRM_MODE_ENCODER_*` type reported by the kernel.
     pub fn kind(&self) -> Kind {
         self.enc_type
     }
@@ -77,9 +78,13 @@ impl Info {
         control::CrtcListFilter(self.pos_crtcs)
     }
 
-    /// Returns a filter for the possible encoders that clones this one.
-    pub fn possible_clones(&self) {
-        unimplemented!()
+    /// Returns a filter for the encoders that can drive the same CRTC as this
+    /// one at the same time, showing the same image.
+    ///
+    /// Use with [`control::ResourceHandles::filter_encoders`]
+    /// to receive a list of encoders.
+    pub fn possible_clones(&self) -> control::EncoderListFilter {
+        control::EncoderListFilter(self.pos_clones)
     }
 }
 
@@ -99,6 +104,24 @@ pub enum Kind {
     DPI,
 }
 
+impl Kind {
+    /// Returns the name of this kind of encoder as used by the kernel, e.g.
+    /// in debugfs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::None => "None",
+            Kind::DAC => "DAC",
+            Kind::TMDS => "TMDS",
+            Kind::LVDS => "LVDS",
+            Kind::TVDAC => "TV",
+            Kind::Virtual => "Virtual",
+            Kind::DSI => "DSI",
+            Kind::DPMST => "DP MST",
+            Kind::DPI => "DPI",
+        }
+    }
+}
+
 impl From<u32> for Kind {
     fn from(n: u32) -> Self {
         match n {
@@ -5,6 +5,7 @@
 use buffer;
 use control;
 use drm_ffi as ffi;
+use drm_ffi::result::SystemError;
 use drm_fourcc::{DrmFourcc, DrmModifier};
 
 /// A handle to a framebuffer
@@ -137,7 +138,286 @@ impl PlanarInfo {
     }
 
     /// Returns the modifier of this framebuffer.
+    ///
+    /// The kernel reports [`DrmModifier::Linear`] for framebuffers created
+    /// without explicit modifiers, see [`PlanarInfo::explicit_modifier`].
     pub fn modifier(&self) -> [DrmModifier; 4] {
         self.modifier
     }
+
+    /// Returns the modifier of the first plane, if the framebuffer was created
+    /// with explicit modifiers.
+    ///
+    /// All planes of a framebuffer share the same modifier. Returns [`None`]
+    /// if the framebuffer lacks the [`ffi::DRM_MODE_FB_MODIFIERS`] flag, in
+    /// which case the driver chose the layout implicitly and it is not
+    /// necessarily linear.
+    pub fn explicit_modifier(&self) -> Option<DrmModifier> {
+        if self.flags & ffi::DRM_MODE_FB_MODIFIERS != 0 {
+            Some(self.modifier[0])
+        } else {
+            None
+        }
+    }
+
+    /// Closes the buffer handles returned alongside this information.
+    ///
+    /// Planes sharing the same buffer share a single handle, which is only
+    /// closed once. If closing a handle fails, the remaining ones are still
+    /// closed and the first error is returned.
+    pub fn close_buffers<D>(&self, card: &D) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let mut result = Ok(());
+        for (i, buffer) in self.buffers.iter().enumerate() {
+            if let Some(buffer) = *buffer {
+                if !self.buffers[..i].contains(&Some(buffer)) {
+                    let closed = card.close_buffer(buffer);
+                    if result.is_ok() {
+                        result = closed;
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Builder for framebuffers made of several planes, e.g. in YUV formats
+///
+/// Planes are added in order with [`FramebufferBuilder::plane`]. When the
+/// framebuffer is created, the number of planes is checked against the
+/// format, so that e.g. a single plane for [`DrmFourcc::Nv12`] is rejected
+/// before reaching the kernel.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct FramebufferBuilder {
+    size: (u32, u32),
+    format: DrmFourcc,
+    planes: usize,
+    handles: [Option<buffer::Handle>; 4],
+    pitches: [u32; 4],
+    offsets: [u32; 4],
+    modifiers: [Option<DrmModifier>; 4],
+}
+
+impl FramebufferBuilder {
+    /// Creates a builder for a framebuffer of the given size and format
+    /// without any planes.
+    pub fn new(size: (u32, u32), format: DrmFourcc) -> FramebufferBuilder {
+        FramebufferBuilder {
+            size,
+            format,
+            planes: 0,
+            handles: [None; 4],
+            pitches: [0; 4],
+            offsets: [0; 4],
+            modifiers: [None; 4],
+        }
+    }
+
+    /// Adds the next plane of the framebuffer.
+    ///
+    /// `modifier` is handled as described in
+    /// [`control::Device::add_planar_framebuffer`].
+    pub fn plane(
+        mut self,
+        handle: buffer::Handle,
+        pitch: u32,
+        offset: u32,
+        modifier: Option<DrmModifier>,
+    ) -> FramebufferBuilder {
+        if self.planes < 4 {
+            self.handles[self.planes] = Some(handle);
+            self.pitches[self.planes] = pitch;
+            self.offsets[self.planes] = offset;
+            self.modifiers[self.planes] = modifier;
+        }
+        self.planes += 1;
+        self
+    }
+
+    /// Creates the framebuffer.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if no plane or more than four
+    /// planes were added, or if the number of planes does not match the
+    /// format. Formats unknown to [`buffer::format::FormatInfo`] accept any
+    /// number of planes.
+    pub fn create<D>(&self, card: &D, flags: u32) -> Result<Handle, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let expected = buffer::format::FormatInfo::new(self.format).map(|info| info.plane_count());
+        if self.planes == 0 || self.planes > 4 || expected.map_or(false, |n| n != self.planes) {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        card.add_planar_framebuffer(self, &self.modifiers, flags)
+    }
+}
+
+impl buffer::PlanarBuffer for FramebufferBuilder {
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn format(&self) -> DrmFourcc {
+        self.format
+    }
+
+    fn pitches(&self) -> [u32; 4] {
+        self.pitches
+    }
+
+    fn handles(&self) -> [Option<buffer::Handle>; 4] {
+        self.handles
+    }
+
+    fn offsets(&self) -> [u32; 4] {
+        self.offsets
+    }
+}
+
+/// Collects the damaged regions of a framebuffer between flushes
+///
+/// Overlapping and adjacent regions are merged into their bounding box as
+/// they are added. If more than [`DamageTracker::max_rects`] regions remain,
+/// or they cover more than [`DamageTracker::max_coverage`] of the
+/// framebuffer, the whole framebuffer is flushed instead, which is cheaper
+/// for the driver than many small copies.
+///
+/// ```
+/// use drm::control::framebuffer::DamageTracker;
+/// use drm::control::ClipRect;
+///
+/// let mut damage = DamageTracker::new((640, 480));
+/// damage.add(ClipRect::new(0, 0, 10, 10));
+/// damage.add(ClipRect::new(10, 0, 20, 10));
+/// damage.add(ClipRect::new(100, 100, 110, 110));
+/// assert_eq!(
+///     damage.rects(),
+///     [ClipRect::new(0, 0, 20, 10), ClipRect::new(100, 100, 110, 110)]
+/// );
+///
+/// damage.add(ClipRect::new(0, 0, 640, 300));
+/// assert_eq!(damage.rects(), [ClipRect::new(0, 0, 640, 480)]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DamageTracker {
+    size: (u16, u16),
+    rects: Vec<control::ClipRect>,
+    max_rects: usize,
+    max_coverage: f64,
+}
+
+impl DamageTracker {
+    /// Creates a tracker for a framebuffer of the given size.
+    ///
+    /// By default, the whole framebuffer is flushed once more than 16
+    /// regions are damaged or they cover more than half of it.
+    pub fn new(size: (u32, u32)) -> DamageTracker {
+        let clamp = |value: u32| value.min(u32::from(u16::MAX)) as u16;
+
+        DamageTracker {
+            size: (clamp(size.0), clamp(size.1)),
+            rects: Vec::new(),
+            max_rects: 16,
+            max_coverage: 0.5,
+        }
+    }
+
+    /// Sets the number of regions above which the whole framebuffer is
+    /// flushed.
+    pub fn max_rects(mut self, max_rects: usize) -> Self {
+        self.max_rects = max_rects;
+        self
+    }
+
+    /// Sets the fraction of the framebuffer, between `0.0` and `1.0`, above
+    /// which the whole framebuffer is flushed.
+    pub fn max_coverage(mut self, max_coverage: f64) -> Self {
+        self.max_coverage = max_coverage;
+        self
+    }
+
+    /// Marks a region as damaged.
+    ///
+    /// The region is clipped to the framebuffer, empty regions are ignored.
+    pub fn add(&mut self, clip: control::ClipRect) {
+        let mut clip = control::ClipRect::new(
+            clip.x1,
+            clip.y1,
+            clip.x2.min(self.size.0),
+            clip.y2.min(self.size.1),
+        );
+        if clip.x1 >= clip.x2 || clip.y1 >= clip.y2 {
+            return;
+        }
+
+        // Merging can make the region touch others it did not touch before
+        while let Some(i) = self.rects.iter().position(|rect| mergeable(rect, &clip)) {
+            let rect = self.rects.swap_remove(i);
+            clip = control::ClipRect::new(
+                clip.x1.min(rect.x1),
+                clip.y1.min(rect.y1),
+                clip.x2.max(rect.x2),
+                clip.y2.max(rect.y2),
+            );
+        }
+        self.rects.push(clip);
+    }
+
+    /// Returns whether no region is damaged.
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// Returns the regions to flush, which is a single region covering the
+    /// whole framebuffer if the thresholds are exceeded.
+    pub fn rects(&self) -> Vec<control::ClipRect> {
+        let area =
+            |rect: &control::ClipRect| f64::from(rect.x2 - rect.x1) * f64::from(rect.y2 - rect.y1);
+        let full = control::ClipRect::new(0, 0, self.size.0, self.size.1);
+        let damaged: f64 = self.rects.iter().map(area).sum();
+
+        if self.rects.len() > self.max_rects || damaged > area(&full) * self.max_coverage {
+            vec![full]
+        } else {
+            self.rects.clone()
+        }
+    }
+
+    /// Forgets all damaged regions.
+    pub fn clear(&mut self) {
+        self.rects.clear();
+    }
+
+    /// Flushes the damaged regions of a framebuffer with
+    /// [`control::Device::flush_framebuffer`] and forgets them.
+    ///
+    /// Does nothing if no region is damaged. The regions are kept if the
+    /// flush fails.
+    pub fn flush<D>(&mut self, card: &D, fb: Handle) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        card.flush_framebuffer(fb, &self.rects())?;
+        self.clear();
+        Ok(())
+    }
+}
+
+/// Returns whether two regions overlap or share an edge.
+fn mergeable(a: &control::ClipRect, b: &control::ClipRect) -> bool {
+    let overlap_x = a.x1 < b.x2 && b.x1 < a.x2;
+    let overlap_y = a.y1 < b.y2 && b.y1 < a.y2;
+    let touch_x = a.x1 <= b.x2 && b.x1 <= a.x2;
+    let touch_y = a.y1 <= b.y2 && b.y1 <= a.y2;
+
+    (overlap_x && touch_y) || (touch_x && overlap_y)
 }
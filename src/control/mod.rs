@@ -34,10 +34,49 @@ use drm_fourcc::{DrmFourcc, DrmModifier, UnrecognizedFourcc};
 
 use bytemuck::allocation::TransparentWrapperAlloc;
 
+/// Defines an enum mirroring the entries of an enum property, along with the
+/// names the kernel uses for the property and each of its entries.
+macro_rules! property_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident [$prop:expr] {
+            $($(#[$vmeta:meta])* $variant:ident => $str:expr,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)+
+        }
+
+        impl $name {
+            /// Get the name of this value as used by the kernel
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $str,)+
+                }
+            }
+        }
+
+        impl $crate::control::PropertyEnum for $name {
+            const PROPERTY_NAME: &'static str = $prop;
+
+            fn from_name(name: &std::ffi::CStr) -> Option<Self> {
+                [$($name::$variant),+]
+                    .iter()
+                    .copied()
+                    .find(|value| $crate::control::name_matches(name, value.as_str()))
+            }
+        }
+    };
+}
+
 pub mod atomic;
+pub mod blob;
 pub mod connector;
 pub mod crtc;
 pub mod dumbbuffer;
+pub mod dump;
 pub mod encoder;
 pub mod framebuffer;
 pub mod plane;
@@ -45,23 +84,27 @@ pub mod plane;
 pub mod property;
 
 use self::dumbbuffer::*;
-use buffer;
+use buffer::{self, FourccExt};
 
 use super::util::*;
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::iter::Zip;
 use std::mem;
 use std::num::NonZeroUsize;
 use std::ops::RangeBounds;
-use std::os::unix::io::{AsRawFd, RawFd};
-use std::time::Duration;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::time::{Duration, Instant};
 
 use core::num::NonZeroU32;
 
 /// Raw handle for a drm resource
 pub type RawResourceHandle = NonZeroU32;
 
+/// Id of a lessee, a DRM client leasing resources from a DRM master
+pub type LesseeId = NonZeroU32;
+
 /// Handle for a drm resource
 pub trait ResourceHandle:
     From<RawResourceHandle> + Into<RawResourceHandle> + Into<u32> + Copy + Sized
@@ -77,6 +120,327 @@ pub fn from_u32<T: From<RawResourceHandle>>(raw: u32) -> Option<T> {
     RawResourceHandle::new(raw).map(T::from)
 }
 
+/// Compares a name reported by the kernel against a known name, ignoring ASCII
+/// case and treating spaces and underscores as equal.
+pub(crate) fn name_matches(name: &std::ffi::CStr, known: &str) -> bool {
+    let normalize = |c: u8| match c {
+        b'_' => b' ',
+        c => c.to_ascii_lowercase(),
+    };
+    let name = name.to_bytes();
+    name.len() == known.len()
+        && name
+            .iter()
+            .zip(known.bytes())
+            .all(|(&a, b)| normalize(a) == normalize(b))
+}
+
+/// Looks up a property of a resource by any of the given names, returning its
+/// information and current value.
+pub(crate) fn find_property<D, T>(
+    card: &D,
+    handle: T,
+    names: &[&str],
+) -> Result<Option<(property::Info, property::RawValue)>, SystemError>
+where
+    D: Device + ?Sized,
+    T: ResourceHandle,
+{
+    for (id, value) in card.get_properties(handle)? {
+        let info = card.get_property(id)?;
+        if names.iter().any(|known| name_matches(info.name(), known)) {
+            return Ok(Some((info, value)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the value to set for a property when capturing the current state in
+/// an atomic request, or [`None`] if atomic commits cannot set the property.
+fn captured_value(info: &property::Info, value: property::RawValue) -> Option<property::RawValue> {
+    // DPMS is only handled by the legacy property ioctl
+    if !info.mutable() || name_matches(info.name(), "DPMS") {
+        return None;
+    }
+
+    if name_matches(info.name(), "Content Protection") {
+        if let property::ValueType::Enum(values) = info.value_type() {
+            let (_, entries) = values.values();
+            let entry = |name| {
+                entries
+                    .iter()
+                    .find(|entry| name_matches(entry.name(), name))
+                    .map(property::EnumValue::value)
+            };
+            // Requesting protection keeps an established one enabled
+            if entry("Enabled") == Some(value) {
+                return entry("Desired");
+            }
+        }
+    }
+
+    Some(value)
+}
+
+/// A typed representation of the entries of an enum property.
+pub(crate) trait PropertyEnum: Copy + PartialEq + Sized {
+    /// Name under which drivers expose the property
+    const PROPERTY_NAME: &'static str;
+
+    /// Parses the name of an entry of the property
+    fn from_name(name: &std::ffi::CStr) -> Option<Self>;
+}
+
+/// Reads the current value of an enum property of a resource.
+///
+/// Returns [`None`] if the resource does not expose the property or its value
+/// is not known to this crate.
+pub(crate) fn get_enum_property<D, T, E>(card: &D, handle: T) -> Result<Option<E>, SystemError>
+where
+    D: Device + ?Sized,
+    T: ResourceHandle,
+    E: PropertyEnum,
+{
+    let value = find_property(card, handle, &[E::PROPERTY_NAME])?
+        .and_then(|(info, value)| enum_value_name(&info, value).and_then(E::from_name));
+
+    Ok(value)
+}
+
+/// Lists the entries of an enum property that a resource supports.
+///
+/// Returns an empty list if the resource does not expose the property.
+pub(crate) fn enum_property_values<D, T, E>(card: &D, handle: T) -> Result<Vec<E>, SystemError>
+where
+    D: Device + ?Sized,
+    T: ResourceHandle,
+    E: PropertyEnum,
+{
+    let values = match find_property(card, handle, &[E::PROPERTY_NAME])? {
+        Some((
+            property::Info {
+                val_type: property::ValueType::Enum(values),
+                ..
+            },
+            _,
+        )) => values
+            .enums
+            .iter()
+            .filter_map(|value| E::from_name(value.name()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(values)
+}
+
+/// Resolves the property handle and raw value needed to set an enum property
+/// of a resource to `value`.
+///
+/// Returns [`SystemError::Unsupported`] if the resource does not expose the
+/// property, or [`SystemError::InvalidArgument`] if it does not support
+/// `value`.
+pub(crate) fn lookup_enum_property<D, T, E>(
+    card: &D,
+    handle: T,
+    value: E,
+) -> Result<(property::Handle, property::RawValue), SystemError>
+where
+    D: Device + ?Sized,
+    T: ResourceHandle,
+    E: PropertyEnum,
+{
+    let (info, _) =
+        find_property(card, handle, &[E::PROPERTY_NAME])?.ok_or(SystemError::Unsupported)?;
+    let raw = find_enum_value(&info, |name| E::from_name(name) == Some(value))
+        .ok_or(SystemError::InvalidArgument)?;
+
+    Ok((info.handle(), raw))
+}
+
+/// Returns the raw value of the first entry of an enum property whose name is
+/// accepted by `filter`.
+pub(crate) fn find_enum_value<F>(info: &property::Info, mut filter: F) -> Option<property::RawValue>
+where
+    F: FnMut(&std::ffi::CStr) -> bool,
+{
+    match &info.val_type {
+        property::ValueType::Enum(values) => values
+            .enums
+            .iter()
+            .find(|value| filter(value.name()))
+            .map(property::EnumValue::value),
+        _ => None,
+    }
+}
+
+/// Returns the name of the entry of an enum property matching `value`.
+pub(crate) fn enum_value_name(
+    info: &property::Info,
+    value: property::RawValue,
+) -> Option<&std::ffi::CStr> {
+    match &info.val_type {
+        property::ValueType::Enum(values) => values
+            .get_value_from_raw_value(value)
+            .map(property::EnumValue::name),
+        _ => None,
+    }
+}
+
+/// Looks up the handle of a property of a resource by name.
+///
+/// Returns [`SystemError::Unsupported`] if the resource does not expose the
+/// property.
+pub(crate) fn property_handle<D, T>(
+    card: &D,
+    handle: T,
+    name: &str,
+) -> Result<property::Handle, SystemError>
+where
+    D: Device + ?Sized,
+    T: ResourceHandle,
+{
+    find_property(card, handle, &[name])?
+        .map(|(info, _)| info.handle())
+        .ok_or(SystemError::Unsupported)
+}
+
+/// Clamps a value to the range of an unsigned range property. Values of other
+/// properties are returned unchanged.
+pub(crate) fn clamp_to_range(
+    info: &property::Info,
+    value: property::RawValue,
+) -> property::RawValue {
+    match info.value_type() {
+        property::ValueType::UnsignedRange(min, max) => value.max(min).min(max),
+        _ => value,
+    }
+}
+
+/// Reads the `type` property of a plane.
+///
+/// Returns [`None`] if the plane does not expose its type.
+pub(crate) fn plane_type<D>(
+    card: &D,
+    plane: plane::Handle,
+) -> Result<Option<PlaneType>, SystemError>
+where
+    D: Device + ?Sized,
+{
+    let plane_type =
+        find_property(card, plane, &["type"])?.and_then(|(_, value)| match value as u32 {
+            ffi::DRM_PLANE_TYPE_OVERLAY => Some(PlaneType::Overlay),
+            ffi::DRM_PLANE_TYPE_PRIMARY => Some(PlaneType::Primary),
+            ffi::DRM_PLANE_TYPE_CURSOR => Some(PlaneType::Cursor),
+            _ => None,
+        });
+
+    Ok(plane_type)
+}
+
+/// Returns the size of a framebuffer.
+///
+/// Buffer handles handed out to the DRM master along with the framebuffer
+/// information are closed again.
+pub(crate) fn framebuffer_size<D>(
+    card: &D,
+    fb: framebuffer::Handle,
+) -> Result<(u32, u32), SystemError>
+where
+    D: Device + ?Sized,
+{
+    match card.get_framebuffer(fb) {
+        Ok(info) => {
+            if let Some(buffer) = info.buffer() {
+                card.close_buffer(buffer)?;
+            }
+            Ok(info.size())
+        }
+        // Multi-planar framebuffers can only be queried with GETFB2
+        Err(SystemError::InvalidArgument) => {
+            let info = card.get_planar_framebuffer(fb)?;
+            info.close_buffers(card)?;
+            Ok(info.size())
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// A route from a CRTC to a connector, through an encoder and optionally a
+/// primary plane
+type Route = (encoder::Handle, crtc::Handle, Option<plane::Handle>);
+
+/// Picks one route from each list of candidates without using an encoder,
+/// CRTC or plane twice, backtracking on conflicts.
+///
+/// A CRTC, along with its primary plane, is only shared by two routes if
+/// `can_clone` allows it for their indices and encoders.
+fn assign_routes<F>(candidates: &[Vec<Route>], chosen: &mut Vec<Route>, can_clone: &F) -> bool
+where
+    F: Fn((usize, encoder::Handle), (usize, encoder::Handle)) -> bool,
+{
+    let index = chosen.len();
+    let routes = match candidates.get(index) {
+        Some(routes) => routes,
+        None => return true,
+    };
+
+    for &(encoder, crtc, plane) in routes {
+        let conflicts = chosen.iter().enumerate().any(|(i, &(e, c, p))| {
+            if e == encoder {
+                true
+            } else if c == crtc {
+                p != plane || !can_clone((i, e), (index, encoder))
+            } else {
+                plane.is_some() && p == plane
+            }
+        });
+        if conflicts {
+            continue;
+        }
+
+        chosen.push((encoder, crtc, plane));
+        if assign_routes(candidates, chosen, can_clone) {
+            return true;
+        }
+        chosen.pop();
+    }
+
+    false
+}
+
+/// The planes of an imported dma-buf, used to create a framebuffer from it.
+struct DmaBufPlanes {
+    size: (u32, u32),
+    format: DrmFourcc,
+    handles: [Option<buffer::Handle>; 4],
+    pitches: [u32; 4],
+    offsets: [u32; 4],
+}
+
+impl buffer::PlanarBuffer for DmaBufPlanes {
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn format(&self) -> DrmFourcc {
+        self.format
+    }
+
+    fn pitches(&self) -> [u32; 4] {
+        self.pitches
+    }
+
+    fn handles(&self) -> [Option<buffer::Handle>; 4] {
+        self.handles
+    }
+
+    fn offsets(&self) -> [u32; 4] {
+        self.offsets
+    }
+}
+
 /// This trait should be implemented by any object that acts as a DRM device and
 /// provides modesetting functionality.
 ///
@@ -174,6 +538,287 @@ pub trait Device: super::Device {
         Ok(connector)
     }
 
+    /// Finds the first connected connector together with its preferred mode and
+    /// a CRTC able to drive it.
+    ///
+    /// The preferred mode is the mode marked with [`ModeTypeFlags::PREFERRED`],
+    /// or the first mode reported if none is marked. If the connector is
+    /// already attached to an encoder and CRTC, those are reused. Otherwise the
+    /// CRTCs possible for the connector's encoders are scanned.
+    ///
+    /// Connectors are not force-probed, see [`Device::get_connector`].
+    /// Returns [`None`] if no connected connector can be driven by any CRTC.
+    fn best_output(&self) -> Result<Option<Output>, SystemError> {
+        let res = self.resource_handles()?;
+
+        for &handle in res.connectors() {
+            let info = self.get_connector(handle, false)?;
+            if info.state() != connector::State::Connected {
+                continue;
+            }
+
+            let mode = match info
+                .preferred_mode()
+                .or_else(|| info.modes().first().copied())
+            {
+                Some(mode) => mode,
+                None => continue,
+            };
+
+            let current = match info.current_encoder() {
+                Some(encoder) => self
+                    .get_encoder(encoder)?
+                    .crtc()
+                    .map(|crtc| (encoder, crtc)),
+                None => None,
+            };
+
+            let route = match current {
+                Some(route) => Some(route),
+                None => {
+                    let mut route = None;
+                    for &encoder in info.encoders() {
+                        let enc_info = self.get_encoder(encoder)?;
+                        if let Some(&crtc) = res.filter_crtcs(enc_info.possible_crtcs()).first() {
+                            route = Some((encoder, crtc));
+                            break;
+                        }
+                    }
+                    route
+                }
+            };
+
+            if let Some((encoder, crtc)) = route {
+                return Ok(Some(Output {
+                    connector: handle,
+                    encoder,
+                    crtc,
+                    mode,
+                }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the connectors whose displays are not meant to show a desktop
+    ///
+    /// See [`connector::Info::is_non_desktop`].
+    fn non_desktop_connectors(&self) -> Result<Vec<connector::Handle>, SystemError> {
+        let res = self.resource_handles()?;
+        let mut connectors = Vec::new();
+
+        for &handle in res.connectors() {
+            if self.get_connector(handle, false)?.is_non_desktop(self)? {
+                connectors.push(handle);
+            }
+        }
+
+        Ok(connectors)
+    }
+
+    /// Returns the mode currently driven on a connector
+    ///
+    /// Returns [`None`] if the connector is not attached to an active CRTC.
+    fn current_mode_for_connector(
+        &self,
+        handle: connector::Handle,
+    ) -> Result<Option<Mode>, SystemError> {
+        let encoder = match self.get_connector(handle, false)?.current_encoder() {
+            Some(encoder) => encoder,
+            None => return Ok(None),
+        };
+
+        match self.get_encoder(encoder)?.crtc() {
+            Some(crtc) => Ok(self.get_crtc(crtc)?.mode()),
+            None => Ok(None),
+        }
+    }
+
+    /// Finds a route from a CRTC to each of the given connectors
+    ///
+    /// Assigns every connector an encoder, a CRTC and, if primary planes are
+    /// exposed (see [`ClientCapability::UniversalPlanes`](crate::ClientCapability::UniversalPlanes)),
+    /// a primary plane, such that no encoder, CRTC or plane is used twice and
+    /// the possible CRTCs of encoders and planes are respected. Connectors
+    /// preferably get their own CRTC. Otherwise, two connectors may share a
+    /// CRTC and its primary plane if their modes have the same timings and
+    /// their encoders list each other in [`encoder::Info::possible_clones`].
+    ///
+    /// Returns the assignments in the order of `outputs`, or
+    /// [`SystemError::Unsupported`] if the hardware cannot drive all
+    /// connectors at once.
+    fn solve_routing(
+        &self,
+        outputs: &[(connector::Handle, Mode)],
+    ) -> Result<Vec<OutputAssignment>, SystemError> {
+        let res = self.resource_handles()?;
+
+        let mut primary_planes = Vec::new();
+        for plane in self.plane_handles()? {
+            if plane_type(self, plane)? == Some(PlaneType::Primary) {
+                let crtcs = self.get_plane(plane)?.supported_crtcs(&res);
+                primary_planes.push((plane, crtcs));
+            }
+        }
+
+        let mut clones = HashMap::new();
+        let mut candidates = Vec::with_capacity(outputs.len());
+        for &(connector, _) in outputs {
+            let mut routes = Vec::new();
+            for &encoder in self.get_connector(connector, false)?.encoders() {
+                let info = self.get_encoder(encoder)?;
+                clones.insert(encoder, res.filter_encoders(info.possible_clones()));
+                for crtc in res.filter_crtcs(info.possible_crtcs()) {
+                    if primary_planes.is_empty() {
+                        routes.push((encoder, crtc, None));
+                    }
+                    for (plane, crtcs) in &primary_planes {
+                        if crtcs.contains(&crtc) {
+                            routes.push((encoder, crtc, Some(*plane)));
+                        }
+                    }
+                }
+            }
+            candidates.push(routes);
+        }
+
+        let clones_of = |a: encoder::Handle, b: encoder::Handle| {
+            clones.get(&a).map_or(false, |clones| clones.contains(&b))
+        };
+        let can_clone = |(i, a): (usize, encoder::Handle), (j, b): (usize, encoder::Handle)| {
+            outputs[i].1.timing_eq(&outputs[j].1) && clones_of(a, b) && clones_of(b, a)
+        };
+
+        let mut chosen = Vec::with_capacity(outputs.len());
+        if !assign_routes(&candidates, &mut chosen, &|_, _| false)
+            && !assign_routes(&candidates, &mut chosen, &can_clone)
+        {
+            return Err(SystemError::Unsupported);
+        }
+
+        let assignments = outputs
+            .iter()
+            .zip(chosen)
+            .map(
+                |(&(connector, mode), (encoder, crtc, plane))| OutputAssignment {
+                    connector,
+                    encoder,
+                    crtc,
+                    plane,
+                    mode,
+                },
+            )
+            .collect();
+
+        Ok(assignments)
+    }
+
+    /// Returns the state of every plane bound to a CRTC
+    ///
+    /// Lets a compositor taking over from another client, e.g. a boot splash,
+    /// replicate the current plane layout in its first commit. Planes without
+    /// a framebuffer or CRTC are skipped. The rectangles are read from the
+    /// atomic plane properties, so [`SystemError::Unsupported`] is returned
+    /// if the atomic client capability is not enabled, see
+    /// [`plane::Info::current_geometry`].
+    fn current_plane_assignments(&self) -> Result<Vec<plane::PlaneAssignment>, SystemError> {
+        let mut assignments = Vec::new();
+
+        for handle in self.plane_handles()? {
+            let info = self.get_plane(handle)?;
+            let (crtc, framebuffer) = match (info.crtc(), info.framebuffer()) {
+                (Some(crtc), Some(framebuffer)) => (crtc, framebuffer),
+                _ => continue,
+            };
+            let geometry = match info.current_geometry(self)? {
+                Some(geometry) => geometry,
+                None => continue,
+            };
+
+            assignments.push(plane::PlaneAssignment {
+                plane: handle,
+                plane_type: plane_type(self, handle)?,
+                crtc,
+                framebuffer,
+                geometry,
+                zpos: find_property(self, handle, &["zpos"])?.map(|(_, zpos)| zpos),
+            });
+        }
+
+        Ok(assignments)
+    }
+
+    /// Groups the connectors driving the tiles of tiled displays
+    ///
+    /// Connectors reporting the same [`connector::TileInfo::group_id`] belong
+    /// to one display, which a compositor can present as a single output of
+    /// [`connector::TiledGroup::size`]. Connectors of displays that are not
+    /// tiled are left out.
+    fn tiled_display_groups(&self) -> Result<Vec<connector::TiledGroup>, SystemError> {
+        let mut groups: Vec<connector::TiledGroup> = Vec::new();
+
+        for &handle in self.resource_handles()?.connectors() {
+            let tile = match self.get_connector(handle, false) {
+                Ok(info) => match info.tile(self)? {
+                    Some(tile) => tile,
+                    None => continue,
+                },
+                // The connector was removed, e.g. by unplugging an MST hub
+                Err(SystemError::Unknown {
+                    errno: nix::errno::Errno::ENOENT,
+                }) => continue,
+                Err(err) => return Err(err),
+            };
+
+            match groups
+                .iter_mut()
+                .find(|group| group.group_id == tile.group_id)
+            {
+                Some(group) => group.tiles.push((handle, tile)),
+                None => groups.push(connector::TiledGroup {
+                    group_id: tile.group_id,
+                    tiles: vec![(handle, tile)],
+                }),
+            }
+        }
+
+        for group in &mut groups {
+            group
+                .tiles
+                .sort_by_key(|(_, tile)| (tile.location.1, tile.location.0));
+        }
+
+        Ok(groups)
+    }
+
+    /// Returns the formats and modifiers supported for scanout by each type
+    /// of plane
+    ///
+    /// Merges [`plane::Info::format_modifiers`] of all planes, so that e.g. a
+    /// renderer can pick a format and modifier for its buffers before
+    /// allocating them. Primary and cursor planes are only included if
+    /// [`ClientCapability::UniversalPlanes`](crate::ClientCapability::UniversalPlanes)
+    /// is enabled; planes without a `type` property count as overlays.
+    fn format_support(&self) -> Result<plane::FormatSupport, SystemError> {
+        let mut support = plane::FormatSupport {
+            primary: Vec::new(),
+            overlay: Vec::new(),
+            cursor: Vec::new(),
+        };
+
+        for handle in self.plane_handles()? {
+            let plane_type = plane_type(self, handle)?.unwrap_or(PlaneType::Overlay);
+            for (format, modifiers) in self.get_plane(handle)?.format_modifiers(self)? {
+                if let Some(format) = DrmFourcc::from_raw_lossy(format) {
+                    support.insert(plane_type, format, &modifiers);
+                }
+            }
+        }
+
+        Ok(support)
+    }
+
     /// Returns information about a specific encoder
     fn get_encoder(&self, handle: encoder::Handle) -> Result<encoder::Info, SystemError> {
         let info = ffi::mode::get_encoder(self.as_fd().as_raw_fd(), handle.into())?;
@@ -189,6 +834,38 @@ pub trait Device: super::Device {
         Ok(enc)
     }
 
+    /// Returns the pipe index of a CRTC, which is its position in the list of
+    /// CRTCs of this device.
+    ///
+    /// Legacy vblank requests identify CRTCs by their pipe index rather than
+    /// their handle.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if the CRTC does not belong to
+    /// this device.
+    fn crtc_pipe_index(&self, crtc: crtc::Handle) -> Result<u32, SystemError> {
+        let res = self.resource_handles()?;
+        res.crtcs()
+            .iter()
+            .position(|&handle| handle == crtc)
+            .map(|index| index as u32)
+            .ok_or(SystemError::InvalidArgument)
+    }
+
+    /// Waits for a vblank on a CRTC.
+    ///
+    /// Like [`super::Device::wait_vblank`], but translates the CRTC handle to
+    /// its pipe index, see [`Device::crtc_pipe_index`].
+    fn wait_crtc_vblank(
+        &self,
+        crtc: crtc::Handle,
+        target_sequence: super::VblankWaitTarget,
+        flags: super::VblankWaitFlags,
+        user_data: usize,
+    ) -> Result<super::VblankWaitReply, SystemError> {
+        let pipe = self.crtc_pipe_index(crtc)?;
+        self.wait_vblank(target_sequence, flags, pipe, user_data)
+    }
+
     /// Returns information about a specific CRTC
     fn get_crtc(&self, handle: crtc::Handle) -> Result<crtc::Info, SystemError> {
         let info = ffi::mode::get_crtc(self.as_fd().as_raw_fd(), handle.into())?;
@@ -207,7 +884,69 @@ pub trait Device: super::Device {
         Ok(crtc)
     }
 
+    /// Returns the connectors currently driven by a CRTC
+    ///
+    /// The CRTC does not report its connectors, so this walks every connector's
+    /// current encoder to find the ones routed to `crtc`. In a cloned
+    /// configuration, all connectors sharing the CRTC are returned. The list is
+    /// empty if the CRTC is not driving any connector.
+    fn connectors_for_crtc(
+        &self,
+        crtc: crtc::Handle,
+    ) -> Result<Vec<connector::Handle>, SystemError> {
+        let res = self.resource_handles()?;
+        let mut connectors = Vec::new();
+
+        for &handle in res.connectors() {
+            let encoder = match self.get_connector(handle, false)?.current_encoder() {
+                Some(encoder) => encoder,
+                None => continue,
+            };
+
+            if self.get_encoder(encoder)?.crtc() == Some(crtc) {
+                connectors.push(handle);
+            }
+        }
+
+        Ok(connectors)
+    }
+
+    /// Returns the CRTCs that are not driving an output, i.e. have neither a
+    /// mode nor a framebuffer set
+    ///
+    /// Useful to pick a CRTC for a new output without taking over one used by
+    /// another output. CRTCs leased to another client are only reported as
+    /// used while the lessee drives them, as a lessor cannot tell which
+    /// objects it leased.
+    fn free_crtcs(&self) -> Result<Vec<crtc::Handle>, SystemError> {
+        let mut free = Vec::new();
+        for &handle in self.resource_handles()?.crtcs() {
+            let info = self.get_crtc(handle)?;
+            if info.mode().is_none() && info.framebuffer().is_none() {
+                free.push(handle);
+            }
+        }
+
+        Ok(free)
+    }
+
+    /// Returns the CRTCs that currently have a mode or a framebuffer set, the
+    /// complement of [`Device::free_crtcs`].
+    fn used_crtcs(&self) -> Result<Vec<crtc::Handle>, SystemError> {
+        let free = self.free_crtcs()?;
+        Ok(self
+            .resource_handles()?
+            .crtcs()
+            .iter()
+            .copied()
+            .filter(|crtc| !free.contains(crtc))
+            .collect())
+    }
+
     /// Set CRTC state
+    ///
+    /// Returns the state of the CRTC after the change, which can differ from
+    /// the requested one if the driver adjusted it.
     fn set_crtc(
         &self,
         handle: crtc::Handle,
@@ -215,8 +954,8 @@ pub trait Device: super::Device {
         pos: (u32, u32),
         conns: &[connector::Handle],
         mode: Option<Mode>,
-    ) -> Result<(), SystemError> {
-        let _info = ffi::mode::set_crtc(
+    ) -> Result<crtc::Info, SystemError> {
+        ffi::mode::set_crtc(
             self.as_fd().as_raw_fd(),
             handle.into(),
             framebuffer.map(Into::into).unwrap_or(0),
@@ -226,10 +965,129 @@ pub trait Device: super::Device {
             mode.map(|m| m.into()),
         )?;
 
+        // The kernel does not report the applied state back through the ioctl
+        self.get_crtc(handle)
+    }
+
+    /// Turns a CRTC off
+    ///
+    /// Detaches the framebuffer and all connectors from the CRTC and clears its
+    /// mode using the legacy API. For atomic commits, see
+    /// [`atomic::AtomicModeReq::disable_crtc`].
+    fn disable_crtc(&self, handle: crtc::Handle) -> Result<(), SystemError> {
+        ffi::mode::set_crtc(self.as_fd().as_raw_fd(), handle.into(), 0, 0, 0, &[], None)?;
         Ok(())
     }
 
+    /// Sets a mode and framebuffer on a CRTC and routes it to connectors
+    ///
+    /// Uses an atomic commit if the atomic client capability is enabled, see
+    /// [`super::Device::set_client_capability`], and [`Device::set_crtc`]
+    /// otherwise. The atomic path scans out the framebuffer on the first
+    /// primary plane usable with the CRTC.
+    ///
+    /// The legacy path can only pan over the framebuffer, so it returns
+    /// [`SystemError::Unsupported`] if the plane rectangles of `config` scale
+    /// the framebuffer or do not cover the whole CRTC.
+    fn modeset(&self, config: &ModesetConfig) -> Result<(), SystemError> {
+        let (src_x, src_y, src_w, src_h) = config.src_rect();
+        let crtc_rect = config.crtc_rect();
+
+        // Atomic properties are only exposed to clients that enabled the
+        // atomic capability
+        if find_property(self, config.crtc, &["ACTIVE"])?.is_none() {
+            let (width, height) = config.mode.size();
+            let (width, height) = (f64::from(width), f64::from(height));
+            if crtc_rect != config.full_crtc_rect()
+                || (src_w, src_h) != (width, height)
+                || src_x.fract() != 0.0
+                || src_y.fract() != 0.0
+                || src_x < 0.0
+                || src_y < 0.0
+            {
+                return Err(SystemError::Unsupported);
+            }
+
+            self.set_crtc(
+                config.crtc,
+                Some(config.framebuffer),
+                (src_x as u32, src_y as u32),
+                &config.connectors,
+                Some(config.mode),
+            )?;
+            return Ok(());
+        }
+
+        if ![src_x, src_y, src_w, src_h]
+            .iter()
+            .all(|v| v.is_finite() && *v >= 0.0)
+        {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        let res = self.resource_handles()?;
+        let mut primary = None;
+        for plane in self.plane_handles()? {
+            let info = self.get_plane(plane)?;
+            if !res
+                .filter_crtcs(info.possible_crtcs())
+                .contains(&config.crtc)
+            {
+                continue;
+            }
+            if plane_type(self, plane)? == Some(PlaneType::Primary) {
+                primary = Some(plane);
+                break;
+            }
+        }
+        let plane = primary.ok_or(SystemError::Unsupported)?;
+
+        let mut req = atomic::AtomicModeReq::new();
+        for &connector in &config.connectors {
+            req.add_property(
+                connector,
+                property_handle(self, connector, "CRTC_ID")?,
+                property::Value::CRTC(Some(config.crtc)),
+            );
+        }
+
+        let to_fixed = |v: f64| u64::from((v * 65536.0).round() as u32);
+        let (crtc_x, crtc_y, crtc_w, crtc_h) = crtc_rect;
+        let plane_props = [
+            ("FB_ID", u64::from(u32::from(config.framebuffer))),
+            ("CRTC_ID", u64::from(u32::from(config.crtc))),
+            ("SRC_X", to_fixed(src_x)),
+            ("SRC_Y", to_fixed(src_y)),
+            ("SRC_W", to_fixed(src_w)),
+            ("SRC_H", to_fixed(src_h)),
+            // Signed range properties take the two's complement
+            ("CRTC_X", crtc_x as i64 as u64),
+            ("CRTC_Y", crtc_y as i64 as u64),
+            ("CRTC_W", u64::from(crtc_w)),
+            ("CRTC_H", u64::from(crtc_h)),
+        ];
+        for &(name, value) in &plane_props {
+            req.add_raw_property(plane.into(), property_handle(self, plane, name)?, value);
+        }
+
+        let active = property_handle(self, config.crtc, "ACTIVE")?;
+        let mode_id = property_handle(self, config.crtc, "MODE_ID")?;
+        let blob = self.create_property_blob(&config.mode)?;
+        req.add_raw_property(config.crtc.into(), active, 1);
+        req.add_property(config.crtc, mode_id, property::Value::Blob(Some(blob)));
+
+        let result = self.atomic_commit(AtomicCommitFlags::ALLOW_MODESET, req);
+        // The CRTC keeps its own reference to the blob
+        let _ = self.destroy_property_blob(blob);
+        result
+    }
+
     /// Returns information about a specific framebuffer
+    ///
+    /// If the caller is the DRM master, the returned buffer handle is a new
+    /// reference owned by the caller, which should be closed with
+    /// [`Device::close_buffer`] once it is no longer needed. Other callers
+    /// receive no handle.
     fn get_framebuffer(
         &self,
         handle: framebuffer::Handle,
@@ -249,6 +1107,11 @@ pub trait Device: super::Device {
     }
 
     /// Returns information about a specific framebuffer (with modifiers)
+    ///
+    /// If the caller is the DRM master, the returned buffer handles are new
+    /// references owned by the caller, which should be closed with
+    /// [`framebuffer::PlanarInfo::close_buffers`] once they are no longer
+    /// needed. Other callers receive no handles, so nothing is leaked.
     fn get_planar_framebuffer(
         &self,
         handle: framebuffer::Handle,
@@ -298,7 +1161,53 @@ pub trait Device: super::Device {
         Ok(from_u32(info.fb_id).unwrap())
     }
 
+    /// Add a framebuffer for a dumb buffer, using the format it was created
+    /// with
+    ///
+    /// Unlike [`Device::add_framebuffer`], the layout is derived from the
+    /// format of the buffer, so the depth and bits-per-pixel cannot mismatch.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if the format is unknown to
+    /// [`buffer::format::FormatInfo`] or has more than one plane, or if the
+    /// pitch or length of the buffer is too small for its size.
+    fn add_framebuffer_for(&self, buffer: &DumbBuffer) -> Result<framebuffer::Handle, SystemError> {
+        let info =
+            buffer::format::FormatInfo::new(buffer.format).ok_or(SystemError::InvalidArgument)?;
+        let (width, height) = buffer.size;
+        let required_length = u64::from(buffer.pitch) * u64::from(height);
+        if info.plane_count() != 1
+            || buffer.pitch < info.min_pitch(0, width)
+            || (buffer.length as u64) < required_length
+        {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        let fb = ffi::mode::add_fb2(
+            self.as_fd().as_raw_fd(),
+            width,
+            height,
+            buffer.format as u32,
+            &[buffer.handle.into(), 0, 0, 0],
+            &[buffer.pitch, 0, 0, 0],
+            &[0; 4],
+            &[0; 4],
+            0,
+        )?;
+
+        Ok(from_u32(fb.fb_id).unwrap())
+    }
+
     /// Add framebuffer (with modifiers)
+    ///
+    /// The [`ffi::DRM_MODE_FB_MODIFIERS`] flag is managed automatically: it is
+    /// only passed to the kernel if at least one plane has a modifier other
+    /// than [`DrmModifier::Invalid`]. Passing [`None`] or
+    /// [`DrmModifier::Invalid`] for every plane lets the driver choose the
+    /// layout of the buffer.
+    ///
+    /// Returns [`SystemError::Unsupported`] if a modifier other than
+    /// [`DrmModifier::Linear`] is requested, but the driver does not support
+    /// framebuffer modifiers (see [`DriverCapability::AddFB2Modifiers`](crate::DriverCapability::AddFB2Modifiers)).
     fn add_planar_framebuffer<B>(
         &self,
         planar_buffer: &B,
@@ -311,13 +1220,37 @@ pub trait Device: super::Device {
         let (w, h) = planar_buffer.size();
         let opt_handles = planar_buffer.handles();
 
+        let explicit_modifiers = modifiers
+            .iter()
+            .flatten()
+            .filter(|&&modifier| modifier != DrmModifier::Invalid);
+        let mut uses_modifiers = false;
+        let mut needs_modifiers = false;
+        for &modifier in explicit_modifiers {
+            uses_modifiers = true;
+            needs_modifiers |= modifier != DrmModifier::Linear;
+        }
+
+        let has_modifiers_cap = uses_modifiers
+            && self.get_driver_capability(super::DriverCapability::AddFB2Modifiers)? != 0;
+        if needs_modifiers && !has_modifiers_cap {
+            return Err(SystemError::Unsupported);
+        }
+
+        // Drivers without modifier support treat buffers as linear by default
+        let (mods, flags) = if has_modifiers_cap {
+            let mods = [
+                modifiers[0].map(Into::<u64>::into).unwrap_or(0),
+                modifiers[1].map(Into::<u64>::into).unwrap_or(0),
+                modifiers[2].map(Into::<u64>::into).unwrap_or(0),
+                modifiers[3].map(Into::<u64>::into).unwrap_or(0),
+            ];
+            (mods, flags | ffi::DRM_MODE_FB_MODIFIERS)
+        } else {
+            ([0; 4], flags & !ffi::DRM_MODE_FB_MODIFIERS)
+        };
+
         let handles = bytemuck::cast(opt_handles);
-        let mods = [
-            modifiers[0].map(Into::<u64>::into).unwrap_or(0),
-            modifiers[1].map(Into::<u64>::into).unwrap_or(0),
-            modifiers[2].map(Into::<u64>::into).unwrap_or(0),
-            modifiers[3].map(Into::<u64>::into).unwrap_or(0),
-        ];
 
         let info = ffi::mode::add_fb2(
             self.as_fd().as_raw_fd(),
@@ -335,20 +1268,144 @@ pub trait Device: super::Device {
     }
 
     /// Mark parts of a framebuffer dirty
+    ///
+    /// Drivers of virtual and remote displays use the clip rects to only
+    /// transfer the changed regions. An empty list marks the whole framebuffer
+    /// dirty.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if a clip rect has a negative
+    /// size. Use [`Device::dirty_framebuffer_checked`] to also check the clip
+    /// rects against the bounds of the framebuffer.
     fn dirty_framebuffer(
         &self,
         handle: framebuffer::Handle,
         clips: &[ClipRect],
     ) -> Result<(), SystemError> {
-        ffi::mode::dirty_fb(self.as_fd().as_raw_fd(), handle.into(), clips)?;
+        self.dirty_framebuffer_annotated(handle, None, clips)
+    }
+
+    /// Mark parts of a framebuffer dirty, checking that the clip rects lie
+    /// within the framebuffer
+    ///
+    /// Like [`Device::dirty_framebuffer`], but returns
+    /// [`SystemError::InvalidArgument`] if a clip rect exceeds the bounds of
+    /// the framebuffer. Querying the size of the framebuffer costs additional
+    /// ioctls, so prefer [`Device::dirty_framebuffer`] when flushing every
+    /// frame.
+    fn dirty_framebuffer_checked(
+        &self,
+        handle: framebuffer::Handle,
+        clips: &[ClipRect],
+    ) -> Result<(), SystemError> {
+        let (width, height) = framebuffer_size(self, handle)?;
+        let in_bounds =
+            |clip: &ClipRect| u32::from(clip.x2) <= width && u32::from(clip.y2) <= height;
+        if !clips.iter().all(in_bounds) {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        self.dirty_framebuffer(handle, clips)
+    }
+
+    /// Mark parts of a framebuffer dirty, with a hint about how they changed
+    ///
+    /// See [`Device::dirty_framebuffer`].
+    fn dirty_framebuffer_annotated(
+        &self,
+        handle: framebuffer::Handle,
+        annotation: Option<DirtyAnnotation>,
+        clips: &[ClipRect],
+    ) -> Result<(), SystemError> {
+        if !clips
+            .iter()
+            .all(|clip| clip.x1 <= clip.x2 && clip.y1 <= clip.y2)
+        {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        let (flags, color) = match annotation {
+            None => (0, 0),
+            Some(DirtyAnnotation::Copy) => (ffi::DRM_MODE_FB_DIRTY_ANNOTATE_COPY, 0),
+            Some(DirtyAnnotation::Fill(color)) => (ffi::DRM_MODE_FB_DIRTY_ANNOTATE_FILL, color),
+        };
+        let clips: Vec<ffi::drm_clip_rect> = clips.iter().map(|&clip| clip.into()).collect();
+
+        ffi::mode::dirty_fb(
+            self.as_fd().as_raw_fd(),
+            handle.into(),
+            flags,
+            color,
+            &clips,
+        )?;
         Ok(())
     }
 
+    /// Pushes CPU rendering into a framebuffer to the display
+    ///
+    /// Virtual and remote display drivers, e.g. virtio-gpu, qxl or DisplayLink,
+    /// keep a copy of the framebuffer that is only updated when parts of it
+    /// are marked dirty, see [`Device::dirty_framebuffer`]. This has to be
+    /// called after drawing into a framebuffer that stays scanned out, i.e.
+    /// when rendering into a single buffer instead of flipping between two.
+    /// Such drivers typically report
+    /// [`DriverCapability::DumbPreferShadow`](crate::DriverCapability::DumbPreferShadow),
+    /// see [`Device::prefers_shadow_buffer`].
+    ///
+    /// On drivers scanning out of the framebuffer directly this does nothing,
+    /// so it is safe to call unconditionally.
+    fn flush_framebuffer(
+        &self,
+        handle: framebuffer::Handle,
+        clips: &[ClipRect],
+    ) -> Result<(), SystemError> {
+        match self.dirty_framebuffer(handle, clips) {
+            // Drivers without a dirty callback show changes immediately
+            Err(SystemError::Unknown {
+                errno: nix::errno::Errno::ENOSYS,
+            }) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Returns whether the driver prefers rendering into a shadow buffer in
+    /// system memory, which is then copied into a dumb buffer
+    ///
+    /// Reading from dumb buffers is slow on these drivers, and changes
+    /// usually have to be flushed with [`Device::flush_framebuffer`].
+    fn prefers_shadow_buffer(&self) -> Result<bool, SystemError> {
+        Ok(self.get_driver_capability(super::DriverCapability::DumbPreferShadow)? != 0)
+    }
+
     /// Destroy a framebuffer
     fn destroy_framebuffer(&self, handle: framebuffer::Handle) -> Result<(), SystemError> {
         ffi::mode::rm_fb(self.as_fd().as_raw_fd(), handle.into())
     }
 
+    /// Destroy several framebuffers
+    ///
+    /// All framebuffers are attempted to be destroyed, even if some of them
+    /// fail. The framebuffers that could not be destroyed are returned along
+    /// with their errors.
+    fn destroy_framebuffers(
+        &self,
+        handles: &[framebuffer::Handle],
+    ) -> Result<(), Vec<(framebuffer::Handle, SystemError)>> {
+        let errors: Vec<_> = handles
+            .iter()
+            .filter_map(|&handle| {
+                self.destroy_framebuffer(handle)
+                    .err()
+                    .map(|err| (handle, err))
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     /// Returns information about a specific plane
     fn get_plane(&self, handle: plane::Handle) -> Result<plane::Info, SystemError> {
         let mut formats = Vec::new();
@@ -361,7 +1418,10 @@ pub trait Device: super::Device {
             crtc: from_u32(info.crtc_id),
             fb: from_u32(info.fb_id),
             pos_crtcs: info.possible_crtcs,
-            formats: unsafe { transmute_vec_from_u32(formats) },
+            formats: formats
+                .into_iter()
+                .filter_map(DrmFourcc::from_raw_lossy)
+                .collect(),
         };
 
         Ok(plane)
@@ -369,8 +1429,75 @@ pub trait Device: super::Device {
 
     /// Set plane state.
     ///
+    /// `crtc_rect` is the position and size of the plane on the CRTC in pixels.
+    /// `src_rect` is the position and size of the region of the framebuffer to
+    /// show in pixels, which can be fractional. It is converted to the 16.16
+    /// fixed point format expected by the kernel.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if the source rectangle is
+    /// negative or not finite. Use [`Device::set_plane_checked`] to also check
+    /// that it fits within the framebuffer.
+    ///
     /// Providing no framebuffer clears the plane.
     fn set_plane(
+        &self,
+        handle: plane::Handle,
+        crtc: crtc::Handle,
+        framebuffer: Option<framebuffer::Handle>,
+        flags: u32,
+        crtc_rect: (i32, i32, u32, u32),
+        src_rect: (f64, f64, f64, f64),
+    ) -> Result<(), SystemError> {
+        let src_rect = match framebuffer {
+            Some(_) => {
+                let (x, y, w, h) = src_rect;
+                if ![x, y, w, h].iter().all(|v| v.is_finite() && *v >= 0.0) {
+                    return Err(SystemError::InvalidArgument);
+                }
+
+                let to_fixed = |v: f64| (v * 65536.0).round() as u32;
+                (to_fixed(x), to_fixed(y), to_fixed(w), to_fixed(h))
+            }
+            None => (0, 0, 0, 0),
+        };
+
+        self.set_plane_fixed(handle, crtc, framebuffer, flags, crtc_rect, src_rect)
+    }
+
+    /// Set plane state, checking that the source rectangle lies within the
+    /// framebuffer.
+    ///
+    /// Like [`Device::set_plane`], but returns [`SystemError::InvalidArgument`]
+    /// if the source rectangle does not fit within the framebuffer. Querying
+    /// the size of the framebuffer costs additional ioctls, so prefer
+    /// [`Device::set_plane`] when updating the plane every frame.
+    fn set_plane_checked(
+        &self,
+        handle: plane::Handle,
+        crtc: crtc::Handle,
+        framebuffer: Option<framebuffer::Handle>,
+        flags: u32,
+        crtc_rect: (i32, i32, u32, u32),
+        src_rect: (f64, f64, f64, f64),
+    ) -> Result<(), SystemError> {
+        if let Some(fb) = framebuffer {
+            let (x, y, w, h) = src_rect;
+            let (fb_w, fb_h) = framebuffer_size(self, fb)?;
+            if x + w > fb_w as f64 || y + h > fb_h as f64 {
+                return Err(SystemError::InvalidArgument);
+            }
+        }
+
+        self.set_plane(handle, crtc, framebuffer, flags, crtc_rect, src_rect)
+    }
+
+    /// Set plane state, with the source rectangle in 16.16 fixed point.
+    ///
+    /// Unlike [`Device::set_plane`], `src_rect` is passed to the kernel as-is:
+    /// each component is the number of pixels multiplied by `65536`.
+    ///
+    /// Providing no framebuffer clears the plane.
+    fn set_plane_fixed(
         &self,
         handle: plane::Handle,
         crtc: crtc::Handle,
@@ -398,6 +1525,31 @@ pub trait Device: super::Device {
         Ok(())
     }
 
+    /// Builds a table of the properties of all connectors, CRTCs and planes,
+    /// so that their handles can be looked up by name without further ioctls.
+    ///
+    /// Primary and cursor planes are only included if
+    /// [`ClientCapability::UniversalPlanes`](crate::ClientCapability::UniversalPlanes)
+    /// is enabled. The table is meant to be built once and kept alongside the
+    /// device.
+    fn property_table(&self) -> Result<property::PropertyTable, SystemError> {
+        let res = self.resource_handles()?;
+        let cache = property::InfoCache::new(self);
+        let mut table = property::PropertyTable::default();
+
+        for &connector in res.connectors() {
+            table.insert(&cache, connector)?;
+        }
+        for &crtc in res.crtcs() {
+            table.insert(&cache, crtc)?;
+        }
+        for plane in self.plane_handles()? {
+            table.insert(&cache, plane)?;
+        }
+
+        Ok(table)
+    }
+
     /// Returns information about a specific property.
     fn get_property(&self, handle: property::Handle) -> Result<property::Info, SystemError> {
         let mut values = Vec::new();
@@ -485,27 +1637,284 @@ pub trait Device: super::Device {
         Ok(())
     }
 
-    /// Create a property blob value from a given data blob
-    fn create_property_blob<T>(&self, data: &T) -> Result<property::Value<'static>, SystemError> {
+    /// Sets the scaling mode of a connector.
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector has no scaling
+    /// mode property, or [`SystemError::InvalidArgument`] if it does not
+    /// support the requested mode.
+    fn set_scaling_mode(
+        &self,
+        handle: connector::Handle,
+        mode: connector::ScalingMode,
+    ) -> Result<(), SystemError> {
+        let (prop, value) = lookup_enum_property(self, handle, mode)?;
+        self.set_property(handle, prop, value)
+    }
+
+    /// Requests or withdraws content protection (HDCP) on a connector through an
+    /// atomic commit.
+    ///
+    /// The kernel reports [`connector::ContentProtection::Enabled`] once
+    /// protection has been established, which can be checked through
+    /// [`connector::Info::content_protection`].
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector does not support
+    /// HDCP.
+    fn request_content_protection(
+        &self,
+        handle: connector::Handle,
+        desired: bool,
+    ) -> Result<(), SystemError> {
+        let state = if desired {
+            connector::ContentProtection::Desired
+        } else {
+            connector::ContentProtection::Undesired
+        };
+        let (prop, value) = lookup_enum_property(self, handle, state)?;
+
+        let mut req = atomic::AtomicModeReq::new();
+        req.add_raw_property(handle.into(), prop, value);
+        self.atomic_commit(AtomicCommitFlags::empty(), req)
+    }
+
+    /// Sets the HDCP content type of a connector through an atomic commit.
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector does not support
+    /// selecting the content type.
+    fn set_hdcp_content_type(
+        &self,
+        handle: connector::Handle,
+        content_type: connector::HdcpContentType,
+    ) -> Result<(), SystemError> {
+        let (prop, value) = lookup_enum_property(self, handle, content_type)?;
+
+        let mut req = atomic::AtomicModeReq::new();
+        req.add_raw_property(handle.into(), prop, value);
+        self.atomic_commit(AtomicCommitFlags::empty(), req)
+    }
+
+    /// Sets the type of content signalled to the display connected to a
+    /// connector through an atomic commit.
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector does not expose
+    /// the property, or [`SystemError::InvalidArgument`] if it does not
+    /// support `content_type`.
+    fn set_content_type(
+        &self,
+        handle: connector::Handle,
+        content_type: connector::ContentType,
+    ) -> Result<(), SystemError> {
+        let (prop, value) = lookup_enum_property(self, handle, content_type)?;
+
+        let mut req = atomic::AtomicModeReq::new();
+        req.add_raw_property(handle.into(), prop, value);
+        self.atomic_commit(AtomicCommitFlags::empty(), req)
+    }
+
+    /// Forces the subconnector used by a connector through an atomic commit,
+    /// e.g. to drive a DVI-I connector with an analog signal.
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector does not expose
+    /// the property, or [`SystemError::InvalidArgument`] if it does not
+    /// support `subconnector`.
+    fn set_subconnector(
+        &self,
+        handle: connector::Handle,
+        subconnector: connector::SelectSubconnector,
+    ) -> Result<(), SystemError> {
+        let (prop, value) = lookup_enum_property(self, handle, subconnector)?;
+
+        let mut req = atomic::AtomicModeReq::new();
+        req.add_raw_property(handle.into(), prop, value);
+        self.atomic_commit(AtomicCommitFlags::empty(), req)
+    }
+
+    /// Resets the link status of a connector to [`connector::LinkStatus::Good`]
+    /// through an atomic commit, after the kernel reported a failed link
+    /// training.
+    ///
+    /// The kernel treats this as a modeset, which retrains the link. Connectors
+    /// without a link status property are left untouched.
+    fn reset_link_status(&self, handle: connector::Handle) -> Result<(), SystemError> {
+        let (info, _) = match find_property(self, handle, &[connector::LinkStatus::PROPERTY_NAME])?
+        {
+            Some(prop) => prop,
+            None => return Ok(()),
+        };
+        let value = find_enum_value(&info, |name| {
+            connector::LinkStatus::from_name(name) == Some(connector::LinkStatus::Good)
+        })
+        .ok_or(SystemError::InvalidArgument)?;
+
+        let mut req = atomic::AtomicModeReq::new();
+        req.add_raw_property(handle.into(), info.handle(), value);
+        self.atomic_commit(AtomicCommitFlags::ALLOW_MODESET, req)
+    }
+
+    /// Sets the RGB quantization range of a connector, e.g. to fix washed out
+    /// colors on HDMI displays expecting full range RGB.
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector does not expose
+    /// the property, or [`SystemError::InvalidArgument`] if it does not
+    /// support `range`.
+    fn set_broadcast_rgb(
+        &self,
+        handle: connector::Handle,
+        range: connector::BroadcastRgb,
+    ) -> Result<(), SystemError> {
+        let (prop, value) = lookup_enum_property(self, handle, range)?;
+        self.set_property(handle, prop, value)
+    }
+
+    /// Sets the colorspace signalled to the display connected to a connector.
+    ///
+    /// This only changes the metadata sent to the display, the content of the
+    /// framebuffers is not converted.
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector does not expose
+    /// the property, or [`SystemError::InvalidArgument`] if it does not
+    /// support `colorspace`.
+    fn set_colorspace(
+        &self,
+        handle: connector::Handle,
+        colorspace: connector::Colorspace,
+    ) -> Result<(), SystemError> {
+        let (prop, value) = lookup_enum_property(self, handle, colorspace)?;
+        self.set_property(handle, prop, value)
+    }
+
+    /// Sets the `max bpc` property of a connector through an atomic commit,
+    /// limiting the bits per color component sent to the display.
+    ///
+    /// `bpc` is clamped to the range supported by the driver, see
+    /// [`connector::Info::max_bpc_range`]. The kernel may need a modeset to
+    /// apply the change, which this commit allows.
+    ///
+    /// Returns [`SystemError::Unsupported`] if the connector does not expose
+    /// the property.
+    fn set_max_bpc(&self, handle: connector::Handle, bpc: u64) -> Result<(), SystemError> {
+        let (info, _) = find_property(self, handle, connector::MAX_BPC_PROPERTY_NAMES)?
+            .ok_or(SystemError::Unsupported)?;
+        let value = match info.value_type() {
+            property::ValueType::UnsignedRange(min, max) => bpc.max(min).min(max),
+            _ => return Err(SystemError::Unsupported),
+        };
+
+        let mut req = atomic::AtomicModeReq::new();
+        req.add_raw_property(handle.into(), info.handle(), value);
+        self.atomic_commit(AtomicCommitFlags::ALLOW_MODESET, req)
+    }
+
+    /// Sets the underscan mode and borders of a connector with an atomic
+    /// commit.
+    ///
+    /// `borders` are the horizontal and vertical borders in pixels, which are
+    /// clamped to the range supported by the driver. Border properties the
+    /// connector does not expose are skipped. Returns
+    /// [`SystemError::Unsupported`] if the connector does not support
+    /// underscan.
+    fn set_underscan(
+        &self,
+        handle: connector::Handle,
+        mode: connector::Underscan,
+        borders: Option<(u64, u64)>,
+    ) -> Result<(), SystemError> {
+        let (prop, value) = lookup_enum_property(self, handle, mode)?;
+        let mut req = atomic::AtomicModeReq::new();
+        req.add_raw_property(handle.into(), prop, value);
+
+        if let Some((hborder, vborder)) = borders {
+            let borders = [
+                (connector::UNDERSCAN_HBORDER_PROPERTY, hborder),
+                (connector::UNDERSCAN_VBORDER_PROPERTY, vborder),
+            ];
+            for &(name, border) in &borders {
+                if let Some((info, _)) = find_property(self, handle, &[name])? {
+                    let border = clamp_to_range(&info, border);
+                    req.add_raw_property(handle.into(), info.handle(), border);
+                }
+            }
+        }
+
+        self.atomic_commit(AtomicCommitFlags::ALLOW_MODESET, req)
+    }
+
+    /// Applies analog TV properties to a connector with an atomic commit.
+    ///
+    /// Only the properties set in `props` are changed. Properties the
+    /// connector does not expose are skipped and range values are clamped to
+    /// the range supported by the driver. Returns
+    /// [`SystemError::InvalidArgument`] if the TV mode is not supported.
+    fn set_tv_properties(
+        &self,
+        handle: connector::Handle,
+        props: &connector::TvProperties,
+    ) -> Result<(), SystemError> {
+        let mut req = atomic::AtomicModeReq::new();
+
+        for (id, _) in self.get_properties(handle)? {
+            let info = self.get_property(id)?;
+            let value = if connector::is_tv_mode_property(&info) {
+                match props.mode() {
+                    Some(mode) => find_enum_value(&info, |name| name.to_bytes() == mode.as_bytes())
+                        .ok_or(SystemError::InvalidArgument)?,
+                    None => continue,
+                }
+            } else {
+                match connector::TvProperty::from_name(info.name()).and_then(|prop| props.get(prop))
+                {
+                    Some(value) => clamp_to_range(&info, value),
+                    None => continue,
+                }
+            };
+            req.add_raw_property(handle.into(), id, value);
+        }
+
+        if req.objects.is_empty() {
+            return Ok(());
+        }
+        self.atomic_commit(AtomicCommitFlags::ALLOW_MODESET, req)
+    }
+
+    /// Create a property blob from a given data blob
+    ///
+    /// The returned handle can be used as a property value through
+    /// [`property::Value::Blob`].
+    fn create_property_blob<T>(&self, data: &T) -> Result<blob::Handle, SystemError> {
         let data = unsafe {
             std::slice::from_raw_parts_mut(data as *const _ as *mut u8, mem::size_of::<T>())
         };
         let blob = ffi::mode::create_property_blob(self.as_fd().as_raw_fd(), data)?;
 
-        Ok(property::Value::Blob(blob.blob_id.into()))
+        from_u32(blob.blob_id).ok_or(SystemError::InvalidArgument)
     }
 
     /// Get a property blob's data
-    fn get_property_blob(&self, blob: u64) -> Result<Vec<u8>, SystemError> {
+    fn get_property_blob(&self, blob: blob::Handle) -> Result<Vec<u8>, SystemError> {
         let mut data = Vec::new();
         let _ =
-            ffi::mode::get_property_blob(self.as_fd().as_raw_fd(), blob as u32, Some(&mut data))?;
+            ffi::mode::get_property_blob(self.as_fd().as_raw_fd(), blob.into(), Some(&mut data))?;
         Ok(data)
     }
 
+    /// Reads a property blob's data into `data`, replacing its contents.
+    ///
+    /// The capacity of `data` is reused, so reading blobs repeatedly, e.g.
+    /// while polling the EDID of a connector, does not reallocate once the
+    /// buffer is large enough.
+    fn get_property_blob_into(
+        &self,
+        blob: blob::Handle,
+        data: &mut Vec<u8>,
+    ) -> Result<(), SystemError> {
+        data.clear();
+        let _ = ffi::mode::get_property_blob(self.as_fd().as_raw_fd(), blob.into(), Some(data))?;
+        Ok(())
+    }
+
     /// Destroy a given property blob value
-    fn destroy_property_blob(&self, blob: u64) -> Result<(), SystemError> {
-        ffi::mode::destroy_property_blob(self.as_fd().as_raw_fd(), blob as u32)?;
+    fn destroy_property_blob(&self, blob: blob::Handle) -> Result<(), SystemError> {
+        ffi::mode::destroy_property_blob(self.as_fd().as_raw_fd(), blob.into())?;
 
         Ok(())
     }
@@ -543,12 +1952,173 @@ pub trait Device: super::Device {
             Some(&mut prop_vals),
         )?;
 
-        let prop_val_set = PropertyValueSet {
-            prop_ids: unsafe { transmute_vec_from_u32(prop_ids) },
-            prop_vals,
-        };
+        let prop_val_set = PropertyValueSet {
+            prop_ids: unsafe { transmute_vec_from_u32(prop_ids) },
+            prop_vals,
+        };
+
+        Ok(prop_val_set)
+    }
+
+    /// Gets a list of property handles and values for a resource of the given
+    /// [`ObjectType`].
+    ///
+    /// Unlike [`Device::get_properties`], this works for any kind of object,
+    /// including framebuffers, blobs and modes, as long as the driver exposes
+    /// properties on it. [`ObjectType::Any`] can be used if the type of the
+    /// resource is unknown.
+    fn get_raw_properties(
+        &self,
+        handle: RawResourceHandle,
+        object_type: ObjectType,
+    ) -> Result<PropertyValueSet, SystemError> {
+        let mut prop_ids = Vec::new();
+        let mut prop_vals = Vec::new();
+
+        ffi::mode::get_properties(
+            self.as_fd().as_raw_fd(),
+            handle.into(),
+            object_type.into(),
+            Some(&mut prop_ids),
+            Some(&mut prop_vals),
+        )?;
+
+        let prop_val_set = PropertyValueSet {
+            prop_ids: unsafe { transmute_vec_from_u32(prop_ids) },
+            prop_vals,
+        };
+
+        Ok(prop_val_set)
+    }
+
+    /// Determines the [`ObjectType`] of a raw resource handle by looking it up
+    /// in the resources and planes of this device.
+    ///
+    /// Only framebuffers, CRTCs, connectors, encoders and planes can be
+    /// detected. Primary and cursor planes are only found if
+    /// [`ClientCapability::UniversalPlanes`](crate::ClientCapability::UniversalPlanes)
+    /// is enabled.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if the handle does not belong
+    /// to any of these resources.
+    fn object_type(&self, handle: RawResourceHandle) -> Result<ObjectType, SystemError> {
+        let res = self.resource_handles()?;
+
+        if res
+            .crtcs
+            .iter()
+            .any(|&crtc| RawResourceHandle::from(crtc) == handle)
+        {
+            return Ok(ObjectType::Crtc);
+        }
+        if res
+            .connectors
+            .iter()
+            .any(|&conn| RawResourceHandle::from(conn) == handle)
+        {
+            return Ok(ObjectType::Connector);
+        }
+        if res
+            .encoders
+            .iter()
+            .any(|&enc| RawResourceHandle::from(enc) == handle)
+        {
+            return Ok(ObjectType::Encoder);
+        }
+        if res
+            .fbs
+            .iter()
+            .any(|&fb| RawResourceHandle::from(fb) == handle)
+        {
+            return Ok(ObjectType::Framebuffer);
+        }
+        if self
+            .plane_handles()?
+            .iter()
+            .any(|&plane| RawResourceHandle::from(plane) == handle)
+        {
+            return Ok(ObjectType::Plane);
+        }
+
+        Err(SystemError::InvalidArgument)
+    }
+
+    /// Gets the property handles and values of a raw resource handle along
+    /// with its [`ObjectType`].
+    ///
+    /// The type is determined with [`Device::object_type`], which only sees
+    /// the leased objects when called by a lessee. If the handle is not
+    /// found there, the object types having properties are tried in turn,
+    /// skipping those the kernel rejects. Returns
+    /// [`SystemError::InvalidArgument`] if no type matches, e.g. because the
+    /// object is not visible to this client.
+    fn get_object_properties(
+        &self,
+        handle: RawResourceHandle,
+    ) -> Result<(ObjectType, PropertyValueSet), SystemError> {
+        match self.object_type(handle) {
+            Ok(object_type) => {
+                return self
+                    .get_raw_properties(handle, object_type)
+                    .map(|props| (object_type, props))
+            }
+            Err(SystemError::InvalidArgument) => {}
+            Err(err) => return Err(err),
+        }
+
+        let candidates = [
+            ObjectType::Crtc,
+            ObjectType::Connector,
+            ObjectType::Plane,
+            ObjectType::Framebuffer,
+        ];
+        for &object_type in &candidates {
+            match self.get_raw_properties(handle, object_type) {
+                Ok(props) => return Ok((object_type, props)),
+                Err(SystemError::InvalidArgument)
+                | Err(SystemError::Unknown {
+                    errno: nix::errno::Errno::ENOENT,
+                }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(SystemError::InvalidArgument)
+    }
+
+    /// Gets the properties of a resource along with their decoded values,
+    /// keyed by property name.
+    ///
+    /// Blobs are decoded like in [`Device::dump_state`]. The information
+    /// about each property is looked up in `cache`, so that it is only
+    /// queried once when reading the properties of several resources. Fails
+    /// with [`SystemError::InvalidArgument`] if `cache` was created for
+    /// another device.
+    fn get_named_properties<T: ResourceHandle>(
+        &self,
+        handle: T,
+        cache: &property::InfoCache<Self>,
+    ) -> Result<HashMap<String, dump::ValueDump>, SystemError> {
+        if !std::ptr::eq(self, cache.card()) {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        dump::named_properties(cache, handle)
+    }
 
-        Ok(prop_val_set)
+    /// Captures the connectors, encoders, CRTCs, planes and framebuffers of
+    /// this device along with the decoded values of their properties.
+    ///
+    /// Blobs are decoded where their format is known, e.g. for `MODE_ID`, and
+    /// kept as raw bytes otherwise. Primary and cursor planes are only included
+    /// if [`ClientCapability::UniversalPlanes`](crate::ClientCapability::UniversalPlanes)
+    /// is enabled, and properties only used by atomic mode setting only if
+    /// [`ClientCapability::Atomic`](crate::ClientCapability::Atomic) is.
+    ///
+    /// With the `serde` feature enabled, the returned [`dump::CardDump`] can be
+    /// serialized.
+    fn dump_state(&self) -> Result<dump::CardDump, SystemError> {
+        dump::CardDump::capture(self)
     }
 
     /// Receive the currently set gamma ramp of a crtc
@@ -607,6 +2177,79 @@ pub trait Device: super::Device {
         Ok(())
     }
 
+    /// Reads the current gamma ramp of a CRTC, sized to
+    /// [`crtc::Info::gamma_length`].
+    ///
+    /// Returns [`SystemError::Unsupported`] if the CRTC has no gamma ramp.
+    fn get_gamma_ramp(&self, crtc: crtc::Handle) -> Result<crtc::GammaRamp, SystemError> {
+        let size = self.get_crtc(crtc)?.gamma_length() as usize;
+        if size == 0 {
+            return Err(SystemError::Unsupported);
+        }
+
+        let mut ramp = crtc::GammaRamp {
+            red: vec![0; size],
+            green: vec![0; size],
+            blue: vec![0; size],
+        };
+        self.get_gamma(crtc, &mut ramp.red, &mut ramp.green, &mut ramp.blue)?;
+
+        Ok(ramp)
+    }
+
+    /// Sets the gamma ramp of a CRTC, e.g. to restore one read with
+    /// [`Device::get_gamma_ramp`].
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if a channel has fewer than
+    /// [`crtc::Info::gamma_length`] entries.
+    fn set_gamma_ramp(
+        &self,
+        crtc: crtc::Handle,
+        ramp: &crtc::GammaRamp,
+    ) -> Result<(), SystemError> {
+        self.set_gamma(crtc, &ramp.red, &ramp.green, &ramp.blue)
+    }
+
+    /// Resets the gamma ramp of a CRTC to identity using the legacy API
+    ///
+    /// Each channel is set to a linear ramp from `0` to `u16::MAX` with
+    /// [`crtc::Info::gamma_length`] entries. Returns
+    /// [`SystemError::Unsupported`] if the CRTC has no gamma ramp.
+    fn reset_gamma(&self, crtc: crtc::Handle) -> Result<(), SystemError> {
+        let size = self.get_crtc(crtc)?.gamma_length() as usize;
+        if size == 0 {
+            return Err(SystemError::Unsupported);
+        }
+
+        let last = (size - 1).max(1) as u64;
+        let ramp: Vec<u16> = (0..size as u64)
+            .map(|i| (i * u64::from(u16::MAX) / last) as u16)
+            .collect();
+
+        self.set_gamma(crtc, &ramp, &ramp, &ramp)
+    }
+
+    /// Resets the color pipeline of a CRTC to identity with an atomic commit
+    ///
+    /// Clears the `DEGAMMA_LUT`, `CTM` and `GAMMA_LUT` properties, which makes
+    /// the hardware pass colors through unchanged. Properties the CRTC does
+    /// not expose are skipped. Returns [`SystemError::Unsupported`] if it
+    /// exposes none of them.
+    fn reset_color_pipeline(&self, crtc: crtc::Handle) -> Result<(), SystemError> {
+        let mut req = atomic::AtomicModeReq::new();
+
+        for name in &["DEGAMMA_LUT", "CTM", "GAMMA_LUT"] {
+            if let Some((info, _)) = find_property(self, crtc, &[name])? {
+                req.add_raw_property(crtc.into(), info.handle(), 0);
+            }
+        }
+
+        if req.objects.is_empty() {
+            return Err(SystemError::Unsupported);
+        }
+        self.atomic_commit(AtomicCommitFlags::empty(), req)
+    }
+
     /// Open a GEM buffer handle by name
     fn open_buffer(&self, name: buffer::Name) -> Result<buffer::Handle, SystemError> {
         let info = drm_ffi::gem::open(self.as_fd().as_raw_fd(), name.into())?;
@@ -634,6 +2277,7 @@ pub trait Device: super::Device {
             length: info.size as usize,
             format,
             pitch: info.pitch,
+            bpp: info.bpp,
             handle: from_u32(info.handle).unwrap(),
         };
 
@@ -660,6 +2304,9 @@ pub trait Device: super::Device {
         let mapping = DumbMapping {
             _phantom: ::std::marker::PhantomData,
             map: unsafe { ::std::slice::from_raw_parts_mut(map as *mut _, buffer.length) },
+            size: buffer.size,
+            pitch: buffer.pitch,
+            bpp: buffer.bpp,
         };
 
         Ok(mapping)
@@ -673,6 +2320,32 @@ pub trait Device: super::Device {
         Ok(())
     }
 
+    /// Free the memory resources of several dumb buffers
+    ///
+    /// All buffers are attempted to be freed, even if some of them fail. The
+    /// handles of the buffers that could not be freed are returned along with
+    /// their errors.
+    fn destroy_dumb_buffers<I>(&self, buffers: I) -> Result<(), Vec<(buffer::Handle, SystemError)>>
+    where
+        I: IntoIterator<Item = DumbBuffer>,
+    {
+        let errors: Vec<_> = buffers
+            .into_iter()
+            .filter_map(|buffer| {
+                let handle = buffer.handle;
+                self.destroy_dumb_buffer(buffer)
+                    .err()
+                    .map(|err| (handle, err))
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     /// Sets a hardware-cursor on the given crtc with the image of a given buffer
     ///
     /// A buffer argument of [`None`] will clear the cursor.
@@ -736,20 +2409,315 @@ pub trait Device: super::Device {
         Ok(())
     }
 
+    /// Adds a user mode to the list of modes of a connector
+    ///
+    /// Current kernels accept this ioctl but ignore it. Build the mode with
+    /// [`ModeBuilder`] and pass it to [`Device::set_crtc`], or store it in
+    /// the `MODE_ID` property of a CRTC, instead.
+    #[deprecated(
+        note = "Usage of deprecated ioctl attach_mode: set a user defined mode through MODE_ID instead"
+    )]
+    #[allow(deprecated)]
+    fn attach_mode(&self, connector: connector::Handle, mode: &Mode) -> Result<(), SystemError> {
+        drm_ffi::mode::attach_mode(self.as_fd().as_raw_fd(), connector.into(), mode.mode)?;
+
+        Ok(())
+    }
+
+    /// Removes a user mode from the list of modes of a connector
+    ///
+    /// Current kernels accept this ioctl but ignore it, see
+    /// [`Device::attach_mode`].
+    #[deprecated(
+        note = "Usage of deprecated ioctl detach_mode: set a user defined mode through MODE_ID instead"
+    )]
+    #[allow(deprecated)]
+    fn detach_mode(&self, connector: connector::Handle, mode: &Mode) -> Result<(), SystemError> {
+        drm_ffi::mode::detach_mode(self.as_fd().as_raw_fd(), connector.into(), mode.mode)?;
+
+        Ok(())
+    }
+
+    /// Enables the client capabilities required for atomic modesetting
+    ///
+    /// Sets [`ClientCapability::UniversalPlanes`](crate::ClientCapability::UniversalPlanes)
+    /// and [`ClientCapability::Atomic`](crate::ClientCapability::Atomic). Without
+    /// them, primary and cursor planes are hidden and atomic commits fail.
+    /// This should be called right after opening the device.
+    ///
+    /// Returns [`SystemError::Unsupported`] if the driver does not support
+    /// atomic modesetting, in which case the legacy API has to be used.
+    fn enable_atomic(&self) -> Result<(), SystemError> {
+        self.set_client_capability(super::ClientCapability::UniversalPlanes, true)?;
+        self.set_client_capability(super::ClientCapability::Atomic, true)
+    }
+
+    /// Returns whether the driver supports atomic modesetting
+    ///
+    /// This does not enable atomic modesetting, see [`Device::enable_atomic`].
+    /// The support is probed through the atomic client capability, which does
+    /// not need the DRM master. Kernels older than 4.2 do not know the
+    /// capability and are reported as supporting atomic modesetting, but
+    /// reject [`Device::enable_atomic`].
+    fn supports_atomic(&self) -> bool {
+        // Drivers without atomic support reject the capability with
+        // EOPNOTSUPP before checking the value. Other drivers reject the out
+        // of range value with EINVAL, without changing the capability.
+        let probe = ffi::drm_set_client_cap {
+            capability: ffi::DRM_CLIENT_CAP_ATOMIC.into(),
+            value: u64::MAX,
+        };
+        let result = unsafe { ffi::ioctl::set_cap(&self.as_fd().as_raw_fd(), &probe) };
+
+        matches!(result, Ok(_) | Err(nix::errno::Errno::EINVAL))
+    }
+
+    /// Builds an atomic request holding the current values of all mutable
+    /// properties of the given objects.
+    ///
+    /// Changing a few properties of the returned request and committing it
+    /// keeps everything else as it is, without having to specify the whole
+    /// state. Properties the kernel rejects in atomic commits are left out:
+    /// immutable properties and the legacy `DPMS` property. A
+    /// `Content Protection` established by the driver is captured as
+    /// requested, as only the driver may set it to enabled.
+    ///
+    /// ```no_run
+    /// # use drm::control::{AtomicCommitFlags, Device as ControlDevice, RawResourceHandle};
+    /// # use std::os::unix::io::{AsFd, BorrowedFd};
+    /// # struct Card(std::fs::File);
+    /// # impl AsFd for Card {
+    /// #     fn as_fd(&self) -> BorrowedFd<'_> {
+    /// #         self.0.as_fd()
+    /// #     }
+    /// # }
+    /// # impl drm::Device for Card {}
+    /// # impl ControlDevice for Card {}
+    /// # let card = Card(std::fs::File::open("/dev/dri/card0").unwrap());
+    /// card.enable_atomic().unwrap();
+    ///
+    /// let res = card.resource_handles().unwrap();
+    /// let planes = card.plane_handles().unwrap();
+    /// let mut objects = Vec::new();
+    /// objects.extend(res.connectors().iter().map(|&conn| RawResourceHandle::from(conn)));
+    /// objects.extend(res.crtcs().iter().map(|&crtc| RawResourceHandle::from(crtc)));
+    /// objects.extend(planes.iter().map(|&plane| RawResourceHandle::from(plane)));
+    ///
+    /// // Committing the captured state unchanged is accepted without a modeset
+    /// let req = card.capture_atomic_state(&objects).unwrap();
+    /// card.atomic_commit(AtomicCommitFlags::TEST_ONLY, req).unwrap();
+    /// ```
+    fn capture_atomic_state(
+        &self,
+        objects: &[RawResourceHandle],
+    ) -> Result<atomic::AtomicModeReq, SystemError> {
+        let infos = property::InfoCache::new(self);
+        let mut req = atomic::AtomicModeReq::new();
+        for &object in objects {
+            for (id, value) in self.get_raw_properties(object, ObjectType::Any)? {
+                if let Some(value) = captured_value(&infos.get(id)?, value) {
+                    req.add_raw_property(object, id, value);
+                }
+            }
+        }
+
+        Ok(req)
+    }
+
     /// Request an atomic commit with given flags and property-value pair for a list of objects.
+    ///
+    /// Requests changing the mode, activating or deactivating a CRTC or
+    /// routing a connector to another CRTC need
+    /// [`AtomicCommitFlags::ALLOW_MODESET`], see
+    /// [`AtomicModeReq::requires_modeset`](atomic::AtomicModeReq::requires_modeset).
+    /// If the flag is missing, [`SystemError::ModesetRequired`] is returned.
+    /// Plain page flips should leave it out, so that they can be combined
+    /// with [`AtomicCommitFlags::NONBLOCK`].
+    ///
+    /// Requests with [`AtomicCommitFlags::PAGE_FLIP_ASYNC`] fail with
+    /// [`SystemError::Unsupported`] if the driver lacks
+    /// [`DriverCapability::AtomicASyncPageFlip`](crate::DriverCapability::AtomicASyncPageFlip),
+    /// and with [`SystemError::AsyncFlipNotAllowed`] if they change anything
+    /// but plane framebuffers or allow a modeset.
     fn atomic_commit(
         &self,
         flags: AtomicCommitFlags,
         mut req: atomic::AtomicModeReq,
     ) -> Result<(), SystemError> {
-        drm_ffi::mode::atomic_commit(
+        let is_async = flags.contains(AtomicCommitFlags::PAGE_FLIP_ASYNC);
+        if is_async && flags.contains(AtomicCommitFlags::ALLOW_MODESET) {
+            return Err(SystemError::AsyncFlipNotAllowed);
+        }
+
+        let result = drm_ffi::mode::atomic_commit(
             self.as_fd().as_raw_fd(),
             flags.bits(),
             unsafe { &mut *(&mut *req.objects as *mut _ as *mut [u32]) },
             &mut req.count_props_per_object,
             unsafe { &mut *(&mut *req.props as *mut _ as *mut [u32]) },
             &mut req.values,
-        )
+        );
+
+        // The kernel reports a missing ALLOW_MODESET flag as a generic
+        // EINVAL. Only look for it after a failure, so that page flips do
+        // not pay for the additional ioctls.
+        match result {
+            Err(SystemError::InvalidArgument)
+                if is_async
+                    && self
+                        .get_driver_capability(super::DriverCapability::AtomicASyncPageFlip)
+                        .map_or(true, |cap| cap == 0) =>
+            {
+                Err(SystemError::Unsupported)
+            }
+            Err(SystemError::InvalidArgument)
+                if is_async && !req.is_async_compatible(self).unwrap_or(true) =>
+            {
+                Err(SystemError::AsyncFlipNotAllowed)
+            }
+            Err(SystemError::InvalidArgument)
+                if !flags.contains(AtomicCommitFlags::ALLOW_MODESET)
+                    && req.requires_modeset(self).unwrap_or(false) =>
+            {
+                Err(SystemError::ModesetRequired)
+            }
+            result => result,
+        }
+    }
+
+    /// Request an atomic commit, retrying with
+    /// [`AtomicCommitFlags::ALLOW_MODESET`] if the kernel rejects it because
+    /// it needs a modeset.
+    ///
+    /// Drivers can require a modeset for requests that only update planes,
+    /// e.g. if the needed bandwidth changes, and report this as a generic
+    /// [`SystemError::InvalidArgument`]. The rejected request is therefore
+    /// tested again with [`AtomicCommitFlags::ALLOW_MODESET`], and only
+    /// committed with it if that test passes. Other invalid requests fail with
+    /// the original error. Escalating causes a visible reconfiguration of the
+    /// outputs, so this is only meant for callers that normally avoid
+    /// modesets but have to tolerate the occasional forced one. Requests that
+    /// already allow modesets or use [`AtomicCommitFlags::PAGE_FLIP_ASYNC`]
+    /// are not retried.
+    ///
+    /// Returns whether the request succeeded as is or had to be escalated.
+    fn atomic_commit_with_escalation(
+        &self,
+        flags: AtomicCommitFlags,
+        req: atomic::AtomicModeReq,
+    ) -> Result<atomic::CommitPath, SystemError> {
+        let escalate = !flags
+            .intersects(AtomicCommitFlags::ALLOW_MODESET | AtomicCommitFlags::PAGE_FLIP_ASYNC);
+
+        match self.atomic_commit(flags, req.clone()) {
+            Ok(()) => Ok(atomic::CommitPath::FastPath),
+            Err(err @ (SystemError::InvalidArgument | SystemError::ModesetRequired))
+                if escalate =>
+            {
+                let test = AtomicCommitFlags::TEST_ONLY | AtomicCommitFlags::ALLOW_MODESET;
+                if self.atomic_commit(test, req.clone()).is_err() {
+                    return Err(err);
+                }
+
+                self.atomic_commit(flags | AtomicCommitFlags::ALLOW_MODESET, req)?;
+                Ok(atomic::CommitPath::Escalated)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Request an atomic commit and return the out-fences requested with
+    /// [`AtomicModeReq::request_out_fence`](atomic::AtomicModeReq::request_out_fence).
+    ///
+    /// Every CRTC gets its own fence, which is signalled once the commit has
+    /// been applied to that CRTC, so that the outputs of a multi-head commit
+    /// can be tracked independently. CRTCs for which the kernel did not create
+    /// a fence, e.g. in a [`AtomicCommitFlags::TEST_ONLY`] commit, are left
+    /// out. Errors are the same as for [`Device::atomic_commit`].
+    fn atomic_commit_with_out_fences(
+        &self,
+        flags: AtomicCommitFlags,
+        mut req: atomic::AtomicModeReq,
+    ) -> Result<Vec<(crtc::Handle, OwnedFd)>, SystemError> {
+        let out_fences = std::mem::take(&mut req.out_fences);
+
+        // The kernel writes each fence through the pointer set as the CRTC's
+        // `OUT_FENCE_PTR`. The slots are allocated up front and not resized,
+        // so the pointers stay valid until the commit returns.
+        let mut slots: Vec<i32> = vec![-1; out_fences.len()];
+        for (&(crtc, prop), slot) in out_fences.iter().zip(slots.iter_mut()) {
+            req.add_raw_property(crtc.into(), prop, slot as *mut i32 as u64);
+        }
+
+        self.atomic_commit(flags, req)?;
+
+        Ok(out_fences
+            .into_iter()
+            .zip(slots)
+            .filter(|&(_, fd)| fd >= 0)
+            .map(|((crtc, _), fd)| (crtc, unsafe { OwnedFd::from_raw_fd(fd) }))
+            .collect())
+    }
+
+    /// Tests an atomic request and, if the kernel rejects it, narrows down
+    /// which entry caused the rejection.
+    ///
+    /// The request is tested with [`AtomicCommitFlags::TEST_ONLY`] added to
+    /// `flags`, so nothing is applied. On failure, growing prefixes of the
+    /// request (ordered by object and property id) are tested to find the
+    /// first entry whose addition gets the request rejected. Entries that are
+    /// only valid together with later entries, e.g. a CRTC's `ACTIVE` and
+    /// `MODE_ID`, can be reported instead of the actual culprit.
+    ///
+    /// This issues several ioctls on failure and is meant for debugging, use
+    /// [`Device::atomic_commit`] to commit requests.
+    fn debug_atomic(
+        &self,
+        flags: AtomicCommitFlags,
+        req: &atomic::AtomicModeReq,
+    ) -> Result<(), atomic::AtomicTestFailure> {
+        let flags = (flags - AtomicCommitFlags::PAGE_FLIP_EVENT) | AtomicCommitFlags::TEST_ONLY;
+        let entries = req.entries();
+
+        let test = |count: usize| {
+            let mut partial = atomic::AtomicModeReq::new();
+            for &(obj, prop, value) in &entries[..count] {
+                partial.add_raw_property(obj, prop, value);
+            }
+            self.atomic_commit(flags, partial)
+        };
+
+        let mut error = match test(entries.len()) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+
+        if let Err(err) = test(0) {
+            return Err(atomic::AtomicTestFailure {
+                error: err,
+                culprit: None,
+                accepted: 0,
+            });
+        }
+
+        // Find the shortest rejected prefix, the empty one being accepted
+        let (mut accepted, mut rejected) = (0, entries.len());
+        while rejected - accepted > 1 {
+            let mid = accepted + (rejected - accepted) / 2;
+            match test(mid) {
+                Ok(()) => accepted = mid,
+                Err(err) => {
+                    rejected = mid;
+                    error = err;
+                }
+            }
+        }
+
+        Err(atomic::AtomicTestFailure {
+            error,
+            culprit: Some(entries[rejected - 1]),
+            accepted,
+        })
     }
 
     /// Convert a prime file descriptor to a GEM buffer handle
@@ -758,10 +2726,152 @@ pub trait Device: super::Device {
         Ok(from_u32(info.handle).unwrap())
     }
 
-    /// Convert a prime file descriptor to a GEM buffer handle
-    fn buffer_to_prime_fd(&self, handle: buffer::Handle, flags: u32) -> Result<RawFd, SystemError> {
+    /// Convert a GEM buffer handle to a prime file descriptor
+    ///
+    /// The returned file descriptor is owned by the caller and closed when
+    /// dropped. Use [`AsRawFd::as_raw_fd`] or
+    /// [`IntoRawFd::into_raw_fd`](std::os::unix::io::IntoRawFd::into_raw_fd)
+    /// to pass it on to APIs expecting a raw file descriptor.
+    fn buffer_to_prime_fd(
+        &self,
+        handle: buffer::Handle,
+        flags: u32,
+    ) -> Result<OwnedFd, SystemError> {
         let info = ffi::gem::handle_to_fd(self.as_fd().as_raw_fd(), handle.into(), flags)?;
-        Ok(info.fd)
+        Ok(unsafe { OwnedFd::from_raw_fd(info.fd) })
+    }
+
+    /// Imports a dma-buf, e.g. exported by a renderer, and creates a
+    /// framebuffer from it.
+    ///
+    /// `fds`, `pitches` and `offsets` describe the planes of the buffer and
+    /// must have the same length of one to four entries, otherwise
+    /// [`SystemError::InvalidArgument`] is returned. Planes may share a file
+    /// descriptor. `modifier` applies to all planes; see
+    /// [`Device::add_planar_framebuffer`] for how it is handled.
+    ///
+    /// Returns the framebuffer along with the GEM handles of the planes, which
+    /// are owned by the caller. Planes sharing a dma-buf share a single
+    /// handle. Once the framebuffer is destroyed with
+    /// [`Device::destroy_framebuffer`], each distinct handle should be closed
+    /// once with [`Device::close_buffer`]. The file descriptors are not
+    /// consumed and may be closed right after the import.
+    fn import_dmabuf_as_framebuffer(
+        &self,
+        fds: &[RawFd],
+        size: (u32, u32),
+        format: DrmFourcc,
+        modifier: Option<DrmModifier>,
+        pitches: &[u32],
+        offsets: &[u32],
+    ) -> Result<(framebuffer::Handle, [Option<buffer::Handle>; 4]), SystemError> {
+        if fds.is_empty()
+            || fds.len() > 4
+            || pitches.len() != fds.len()
+            || offsets.len() != fds.len()
+        {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        let mut planes = DmaBufPlanes {
+            size,
+            format,
+            handles: [None; 4],
+            pitches: [0; 4],
+            offsets: [0; 4],
+        };
+        planes.pitches[..pitches.len()].copy_from_slice(pitches);
+        planes.offsets[..offsets.len()].copy_from_slice(offsets);
+
+        let close_handles = |handles: &[Option<buffer::Handle>]| {
+            for (i, handle) in handles.iter().enumerate() {
+                if let Some(handle) = *handle {
+                    if !handles[..i].contains(&Some(handle)) {
+                        let _ = self.close_buffer(handle);
+                    }
+                }
+            }
+        };
+
+        for (i, &fd) in fds.iter().enumerate() {
+            match self.prime_fd_to_buffer(fd) {
+                Ok(handle) => planes.handles[i] = Some(handle),
+                Err(err) => {
+                    close_handles(&planes.handles[..i]);
+                    return Err(err);
+                }
+            }
+        }
+
+        let mut modifiers = [None; 4];
+        for plane_modifier in modifiers.iter_mut().take(fds.len()) {
+            *plane_modifier = modifier;
+        }
+
+        match self.add_planar_framebuffer(&planes, &modifiers, 0) {
+            Ok(fb) => Ok((fb, planes.handles)),
+            Err(err) => {
+                close_handles(&planes.handles);
+                Err(err)
+            }
+        }
+    }
+
+    /// Leases the given objects to a new lessee.
+    ///
+    /// Returns the id of the lessee along with a file descriptor, which gives
+    /// access to the leased objects and can be passed to another process. The
+    /// file descriptor is owned by the caller and closed when dropped. `flags`
+    /// apply to the returned file descriptor, see [`LeaseFlags`].
+    ///
+    /// The file descriptor can also be used within the same process, e.g. by
+    /// wrapping it in another type implementing [`Device`]. It refers to the
+    /// same device, but only exposes the leased objects, see
+    /// [`Device::get_lease`].
+    fn create_lease(
+        &self,
+        objects: &[RawResourceHandle],
+        flags: LeaseFlags,
+    ) -> Result<(LesseeId, OwnedFd), SystemError> {
+        let lease = ffi::mode::create_lease(
+            self.as_fd().as_raw_fd(),
+            unsafe { &*(objects as *const _ as *const [u32]) },
+            flags.bits(),
+        )?;
+
+        let fd = unsafe { OwnedFd::from_raw_fd(lease.fd as RawFd) };
+        let lessee_id = NonZeroU32::new(lease.lessee_id).ok_or(SystemError::InvalidArgument)?;
+        Ok((lessee_id, fd))
+    }
+
+    /// Lists the lessees of this DRM master.
+    fn list_lessees(&self) -> Result<Vec<LesseeId>, SystemError> {
+        let mut lessees = Vec::new();
+        ffi::mode::list_lessees(self.as_fd().as_raw_fd(), Some(&mut lessees))?;
+        Ok(unsafe { transmute_vec_from_u32(lessees) })
+    }
+
+    /// Returns whether a lessee of this DRM master exists, e.g. to validate
+    /// an id before revoking its lease.
+    fn lease_exists(&self, lessee_id: LesseeId) -> Result<bool, SystemError> {
+        Ok(self.list_lessees()?.contains(&lessee_id))
+    }
+
+    /// Returns the objects leased to this device, if it was opened through a
+    /// lease.
+    ///
+    /// A lessee only sees the leased objects in [`Device::resource_handles`]
+    /// and [`Device::plane_handles`], and the CRTC filters of its encoders
+    /// and planes refer to that reduced list of CRTCs.
+    fn get_lease(&self) -> Result<Vec<RawResourceHandle>, SystemError> {
+        let mut objects = Vec::new();
+        ffi::mode::get_lease(self.as_fd().as_raw_fd(), Some(&mut objects))?;
+        Ok(unsafe { transmute_vec_from_u32(objects) })
+    }
+
+    /// Revokes a lease, removing access to the leased objects from the lessee.
+    fn revoke_lease(&self, lessee_id: LesseeId) -> Result<(), SystemError> {
+        ffi::mode::revoke_lease(self.as_fd().as_raw_fd(), lessee_id.get())
     }
 
     /// Queue a page flip on the given crtc
@@ -798,7 +2908,21 @@ pub trait Device: super::Device {
     }
 
     /// Receive pending events
+    ///
+    /// The timestamps of the events are assumed to be taken from
+    /// [`TimestampClock::Monotonic`], which all but very old kernels use. To
+    /// support those, query [`Device::timestamp_clock`] once and use
+    /// [`Device::receive_events_with_clock`].
     fn receive_events(&self) -> Result<Events, SystemError>
+    where
+        Self: Sized,
+    {
+        self.receive_events_with_clock(TimestampClock::Monotonic)
+    }
+
+    /// Receive pending events whose timestamps are taken from `clock`, see
+    /// [`Device::timestamp_clock`].
+    fn receive_events_with_clock(&self, clock: TimestampClock) -> Result<Events, SystemError>
     where
         Self: Sized,
     {
@@ -809,8 +2933,56 @@ pub trait Device: super::Device {
             event_buf,
             amount,
             i: 0,
+            clock,
         })
     }
+
+    /// Returns the clock the timestamps of events are taken from
+    ///
+    /// This queries the device, so it is meant to be called once, e.g. right
+    /// after opening the device, and not for every event.
+    fn timestamp_clock(&self) -> TimestampClock {
+        // Kernels without the capability report wall clock timestamps
+        match self.get_driver_capability(super::DriverCapability::MonotonicTimestamp) {
+            Ok(0) | Err(_) => TimestampClock::Realtime,
+            Ok(_) => TimestampClock::Monotonic,
+        }
+    }
+
+    /// Waits for events to become pending and receives them.
+    ///
+    /// Returns [`None`] if no event arrived before `timeout` elapsed, or waits
+    /// indefinitely if no timeout is given. Interrupted waits are resumed with
+    /// the remaining time.
+    ///
+    /// The kernel hands out all pending events at once, so the returned
+    /// [`Events`] can contain more than one event. This is useful for devices
+    /// opened with `O_NONBLOCK`, e.g. through [`crate::node::open`], on which
+    /// [`Device::receive_events`] does not block. Like that function, this
+    /// assumes that timestamps are taken from [`TimestampClock::Monotonic`].
+    fn wait_events(&self, timeout: Option<Duration>) -> Result<Option<Events>, SystemError>
+    where
+        Self: Sized,
+    {
+        use nix::errno::Errno;
+        use nix::poll::{ppoll, PollFd, PollFlags};
+        use nix::sys::time::TimeSpec;
+
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            let mut fds = [PollFd::new(self.as_fd().as_raw_fd(), PollFlags::POLLIN)];
+
+            match ppoll(&mut fds, remaining.map(TimeSpec::from), None) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return self.receive_events().map(Some),
+                Err(Errno::EINTR) => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
 }
 
 bitflags::bitflags! {
@@ -827,6 +2999,32 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// Flags of the file descriptor returned by [`Device::create_lease`]
+    ///
+    /// The kernel rejects any other flag.
+    pub struct LeaseFlags : u32 {
+        /// Close the file descriptor on `exec`
+        const CLOEXEC = nix::libc::O_CLOEXEC as u32;
+        /// Open the file descriptor in non-blocking mode, so that reading
+        /// events fails instead of waiting if none are pending
+        const NONBLOCK = nix::libc::O_NONBLOCK as u32;
+    }
+}
+
+impl From<u32> for LeaseFlags {
+    /// Unknown bits are discarded.
+    fn from(bits: u32) -> Self {
+        LeaseFlags::from_bits_truncate(bits)
+    }
+}
+
+impl From<LeaseFlags> for u32 {
+    fn from(flags: LeaseFlags) -> Self {
+        flags.bits()
+    }
+}
+
 /// Target to alter the sequence of page flips
 ///
 /// These represent the [`ffi::drm_sys::DRM_MODE_PAGE_FLIP_TARGET`] bits
@@ -845,6 +3043,40 @@ pub struct Events {
     event_buf: [u8; 1024],
     amount: usize,
     i: usize,
+    clock: TimestampClock,
+}
+
+/// The clock the timestamps of events are taken from
+///
+/// Depends on [`super::DriverCapability::MonotonicTimestamp`], which is set
+/// on all but very old kernels.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum TimestampClock {
+    /// `CLOCK_MONOTONIC`, comparable with [`Instant`]
+    Monotonic,
+    /// `CLOCK_REALTIME`, the wall clock time since the Unix epoch
+    Realtime,
+}
+
+impl TimestampClock {
+    /// Converts a timestamp of this clock to an [`Instant`].
+    ///
+    /// Returns [`None`] for [`TimestampClock::Realtime`], as the wall clock
+    /// can jump, or if the timestamp cannot be represented.
+    pub fn to_instant(&self, time: Duration) -> Option<Instant> {
+        use nix::time::{clock_gettime, ClockId};
+
+        if *self != TimestampClock::Monotonic {
+            return None;
+        }
+
+        let now = Instant::now();
+        let monotonic_now = Duration::from(clock_gettime(ClockId::CLOCK_MONOTONIC).ok()?);
+        match monotonic_now.checked_sub(time) {
+            Some(elapsed) => now.checked_sub(elapsed),
+            None => now.checked_add(time - monotonic_now),
+        }
+    }
 }
 
 /// An event from a device.
@@ -863,69 +3095,198 @@ pub struct VblankEvent {
     pub frame: u32,
     /// time at which the vblank occurred
     pub time: Duration,
+    /// clock `time` is taken from
+    pub clock: TimestampClock,
     /// crtc that did throw the event
     pub crtc: crtc::Handle,
     /// user data that was passed to wait_vblank
     pub user_data: usize,
 }
 
-/// Page Flip event
-pub struct PageFlipEvent {
-    /// sequence of the frame
-    pub frame: u32,
-    /// duration between events
-    pub duration: Duration,
-    /// crtc that did throw the event
-    pub crtc: crtc::Handle,
-}
+impl VblankEvent {
+    /// Returns the time at which the vblank occurred as an [`Instant`], if
+    /// the timestamp is monotonic.
+    pub fn instant(&self) -> Option<Instant> {
+        self.clock.to_instant(self.time)
+    }
+}
+
+/// Page Flip event
+pub struct PageFlipEvent {
+    /// sequence of the frame
+    pub frame: u32,
+    /// time at which the flip completed
+    pub duration: Duration,
+    /// clock `duration` is taken from
+    pub clock: TimestampClock,
+    /// crtc that did throw the event
+    pub crtc: crtc::Handle,
+}
+
+impl PageFlipEvent {
+    /// Returns the time at which the flip completed as an [`Instant`], if the
+    /// timestamp is monotonic.
+    pub fn instant(&self) -> Option<Instant> {
+        self.clock.to_instant(self.duration)
+    }
+}
+
+impl Iterator for Events {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        let (event, length) = parse_event(&self.event_buf[self.i..self.amount], self.clock)?;
+        self.i += length;
+        Some(event)
+    }
+}
+
+/// Decodes the event at the start of `data`, returning it along with its
+/// length, or [`None`] if `data` does not hold a complete event.
+fn parse_event(data: &[u8], clock: TimestampClock) -> Option<(Event, usize)> {
+    if data.len() < mem::size_of::<ffi::drm_event>() {
+        return None;
+    }
+    let header: ffi::drm_event = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const _) };
+    let length = header.length as usize;
+    if length < mem::size_of::<ffi::drm_event>() || data.len() < length {
+        return None;
+    }
+
+    let event = match header.type_ {
+        ffi::DRM_EVENT_VBLANK | ffi::DRM_EVENT_FLIP_COMPLETE
+            if length >= mem::size_of::<ffi::drm_event_vblank>() =>
+        {
+            let vblank_event: ffi::drm_event_vblank =
+                unsafe { std::ptr::read_unaligned(data.as_ptr() as *const _) };
+            let time = Duration::new(vblank_event.tv_sec as u64, vblank_event.tv_usec * 1000);
+
+            if header.type_ == ffi::DRM_EVENT_VBLANK {
+                Event::Vblank(VblankEvent {
+                    frame: vblank_event.sequence,
+                    time,
+                    clock,
+                    #[allow(clippy::unnecessary_cast)]
+                    crtc: from_u32(vblank_event.crtc_id as u32).unwrap(),
+                    user_data: vblank_event.user_data as usize,
+                })
+            } else {
+                Event::PageFlip(PageFlipEvent {
+                    frame: vblank_event.sequence,
+                    duration: time,
+                    clock,
+                    crtc: from_u32(if vblank_event.crtc_id != 0 {
+                        vblank_event.crtc_id
+                    } else {
+                        vblank_event.user_data as u32
+                    })
+                    .unwrap(),
+                })
+            }
+        }
+        _ => Event::Unknown(data[..length].to_vec()),
+    };
+
+    Some((event, length))
+}
+
+/// Reassembles events from reads of a device.
+///
+/// Unlike [`Events`], which only holds the data of a single read, the reader
+/// keeps incomplete events in a growable buffer until the rest of their data
+/// arrives, so no event is lost if a read ends in the middle of one.
+///
+/// # Example
+///
+/// ```
+/// # extern crate drm;
+/// # extern crate drm_ffi;
+/// use drm::control::{Event, EventReader, TimestampClock};
+///
+/// // A page flip event on CRTC 42
+/// let mut event = Vec::new();
+/// event.extend_from_slice(&drm_ffi::DRM_EVENT_FLIP_COMPLETE.to_ne_bytes());
+/// event.extend_from_slice(&32u32.to_ne_bytes());
+/// event.extend_from_slice(&0u64.to_ne_bytes());
+/// for field in &[1u32, 500, 7, 42] {
+///     event.extend_from_slice(&field.to_ne_bytes());
+/// }
+///
+/// let mut reader = EventReader::new(TimestampClock::Monotonic);
+///
+/// // The header is split across two reads
+/// reader.feed(&event[..5]);
+/// assert!(reader.next_event().is_none());
+/// reader.feed(&event[5..20]);
+/// assert!(reader.next_event().is_none());
+/// reader.feed(&event[20..]);
+/// reader.feed(&event[..3]);
+///
+/// match reader.next_event() {
+///     Some(Event::PageFlip(flip)) => {
+///         assert_eq!(flip.frame, 7);
+///         assert_eq!(u32::from(flip.crtc), 42);
+///     }
+///     _ => panic!("expected a page flip event"),
+/// }
+/// assert!(reader.next_event().is_none());
+///
+/// reader.feed(&event[3..]);
+/// assert!(reader.next_event().is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventReader {
+    buffer: Vec<u8>,
+    clock: TimestampClock,
+}
+
+impl EventReader {
+    /// Size of the reads done by [`EventReader::read`]
+    const READ_SIZE: usize = 4096;
 
-impl Iterator for Events {
-    type Item = Event;
+    /// Creates an empty reader for events with timestamps of `clock`, see
+    /// [`Device::timestamp_clock`].
+    pub fn new(clock: TimestampClock) -> EventReader {
+        EventReader {
+            buffer: Vec::new(),
+            clock,
+        }
+    }
 
-    fn next(&mut self) -> Option<Event> {
-        if self.amount > 0 && self.i < self.amount {
-            let event = unsafe { &*(self.event_buf.as_ptr().add(self.i) as *const ffi::drm_event) };
-            self.i += event.length as usize;
-            match event.type_ {
-                ffi::DRM_EVENT_VBLANK => {
-                    let vblank_event =
-                        unsafe { &*(event as *const _ as *const ffi::drm_event_vblank) };
-                    Some(Event::Vblank(VblankEvent {
-                        frame: vblank_event.sequence,
-                        time: Duration::new(
-                            vblank_event.tv_sec as u64,
-                            vblank_event.tv_usec * 1000,
-                        ),
-                        #[allow(clippy::unnecessary_cast)]
-                        crtc: from_u32(vblank_event.crtc_id as u32).unwrap(),
-                        user_data: vblank_event.user_data as usize,
-                    }))
-                }
-                ffi::DRM_EVENT_FLIP_COMPLETE => {
-                    let vblank_event =
-                        unsafe { &*(event as *const _ as *const ffi::drm_event_vblank) };
-                    Some(Event::PageFlip(PageFlipEvent {
-                        frame: vblank_event.sequence,
-                        duration: Duration::new(
-                            vblank_event.tv_sec as u64,
-                            vblank_event.tv_usec * 1000,
-                        ),
-                        crtc: from_u32(if vblank_event.crtc_id != 0 {
-                            vblank_event.crtc_id
-                        } else {
-                            vblank_event.user_data as u32
-                        })
-                        .unwrap(),
-                    }))
-                }
-                _ => Some(Event::Unknown(
-                    self.event_buf[self.i - (event.length as usize)..self.i].to_vec(),
-                )),
+    /// Reads pending events from the device into the buffer.
+    ///
+    /// Returns the number of bytes read. Like [`Device::receive_events`],
+    /// this blocks until events arrive unless the device was opened with
+    /// `O_NONBLOCK`.
+    pub fn read<D: Device + ?Sized>(&mut self, card: &D) -> Result<usize, SystemError> {
+        let start = self.buffer.len();
+        self.buffer.resize(start + Self::READ_SIZE, 0);
+
+        match ::nix::unistd::read(card.as_fd().as_raw_fd(), &mut self.buffer[start..]) {
+            Ok(amount) => {
+                self.buffer.truncate(start + amount);
+                Ok(amount)
+            }
+            Err(err) => {
+                self.buffer.truncate(start);
+                Err(err.into())
             }
-        } else {
-            None
         }
     }
+
+    /// Appends raw event data to the buffer, e.g. read from the device by
+    /// other means.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next complete event in the buffer, or [`None`] if more data
+    /// is needed.
+    pub fn next_event(&mut self) -> Option<Event> {
+        let (event, length) = parse_event(&self.buffer, self.clock)?;
+        self.buffer.drain(..length);
+        Some(event)
+    }
 }
 
 /// The set of [`ResourceHandles`] that a
@@ -976,22 +3337,239 @@ impl ResourceHandles {
     }
 
     /// Apply a filter the all crtcs of these resources, resulting in a list of crtcs allowed.
+    ///
+    /// The filter must come from the same file descriptor as these resources.
+    /// On a lease, the kernel only lists the leased CRTCs and renumbers the
+    /// bits of the filters it reports to match, so filters of a lessee and of
+    /// the lessor cannot be mixed.
     pub fn filter_crtcs(&self, filter: CrtcListFilter) -> Vec<crtc::Handle> {
         self.crtcs
             .iter()
             .enumerate()
-            .filter(|&(n, _)| (1 << n) & filter.0 != 0)
+            .filter(|&(n, _)| filter.contains_index(n))
             .map(|(_, &e)| e)
             .collect()
     }
+
+    /// Apply a filter to all encoders of these resources, resulting in a list
+    /// of encoders allowed.
+    ///
+    /// The filter must come from the same file descriptor as these resources.
+    pub fn filter_encoders(&self, filter: EncoderListFilter) -> Vec<encoder::Handle> {
+        self.encoders
+            .iter()
+            .enumerate()
+            .filter(|&(n, _)| filter.contains_index(n))
+            .map(|(_, &e)| e)
+            .collect()
+    }
+}
+
+/// A connected connector, along with a mode and a route through an encoder and
+/// CRTC that can display it. Create via [`Device::best_output()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Output {
+    pub(crate) connector: connector::Handle,
+    pub(crate) encoder: encoder::Handle,
+    pub(crate) crtc: crtc::Handle,
+    pub(crate) mode: Mode,
+}
+
+impl Output {
+    /// Returns the handle to the connector.
+    pub fn connector(&self) -> connector::Handle {
+        self.connector
+    }
+
+    /// Returns the handle to the encoder routing the CRTC to the connector.
+    pub fn encoder(&self) -> encoder::Handle {
+        self.encoder
+    }
+
+    /// Returns the handle to the CRTC driving the connector.
+    pub fn crtc(&self) -> crtc::Handle {
+        self.crtc
+    }
+
+    /// Returns the preferred mode of the connector.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+}
+
+/// The configuration of a CRTC, applied with [`Device::modeset`]
+///
+/// By default the primary plane covers the whole CRTC and shows the region of
+/// the framebuffer of the size of the mode at its top left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModesetConfig {
+    pub(crate) crtc: crtc::Handle,
+    pub(crate) connectors: Vec<connector::Handle>,
+    pub(crate) mode: Mode,
+    pub(crate) framebuffer: framebuffer::Handle,
+    pub(crate) src_rect: Option<(f64, f64, f64, f64)>,
+    pub(crate) crtc_rect: Option<(i32, i32, u32, u32)>,
+}
+
+impl ModesetConfig {
+    /// Creates a configuration driving `connectors` from `crtc` with `mode`,
+    /// scanning out `framebuffer`.
+    pub fn new(
+        crtc: crtc::Handle,
+        connectors: &[connector::Handle],
+        mode: Mode,
+        framebuffer: framebuffer::Handle,
+    ) -> ModesetConfig {
+        ModesetConfig {
+            crtc,
+            connectors: connectors.to_vec(),
+            mode,
+            framebuffer,
+            src_rect: None,
+            crtc_rect: None,
+        }
+    }
+
+    /// Sets the region of the framebuffer to show as `(x, y, width, height)`
+    /// in pixels, which can be fractional.
+    pub fn set_src_rect(&mut self, src_rect: (f64, f64, f64, f64)) -> &mut Self {
+        self.src_rect = Some(src_rect);
+        self
+    }
+
+    /// Sets the position and size of the primary plane on the CRTC as
+    /// `(x, y, width, height)` in pixels.
+    pub fn set_crtc_rect(&mut self, crtc_rect: (i32, i32, u32, u32)) -> &mut Self {
+        self.crtc_rect = Some(crtc_rect);
+        self
+    }
+
+    /// Returns the handle to the CRTC.
+    pub fn crtc(&self) -> crtc::Handle {
+        self.crtc
+    }
+
+    /// Returns the connectors driven by the CRTC.
+    pub fn connectors(&self) -> &[connector::Handle] {
+        &self.connectors
+    }
+
+    /// Returns the mode of the CRTC.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns the framebuffer to scan out.
+    pub fn framebuffer(&self) -> framebuffer::Handle {
+        self.framebuffer
+    }
+
+    /// Returns the region of the framebuffer to show.
+    pub fn src_rect(&self) -> (f64, f64, f64, f64) {
+        self.src_rect.unwrap_or_else(|| {
+            let (width, height) = self.mode.size();
+            (0.0, 0.0, f64::from(width), f64::from(height))
+        })
+    }
+
+    /// Returns the position and size of the primary plane on the CRTC.
+    pub fn crtc_rect(&self) -> (i32, i32, u32, u32) {
+        self.crtc_rect.unwrap_or_else(|| self.full_crtc_rect())
+    }
+
+    fn full_crtc_rect(&self) -> (i32, i32, u32, u32) {
+        let (width, height) = self.mode.size();
+        (0, 0, width.into(), height.into())
+    }
+}
+
+/// The route assigned to a connector by [`Device::solve_routing`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutputAssignment {
+    pub(crate) connector: connector::Handle,
+    pub(crate) encoder: encoder::Handle,
+    pub(crate) crtc: crtc::Handle,
+    pub(crate) plane: Option<plane::Handle>,
+    pub(crate) mode: Mode,
+}
+
+impl OutputAssignment {
+    /// Returns the handle to the connector.
+    pub fn connector(&self) -> connector::Handle {
+        self.connector
+    }
+
+    /// Returns the handle to the encoder routing the CRTC to the connector.
+    pub fn encoder(&self) -> encoder::Handle {
+        self.encoder
+    }
+
+    /// Returns the handle to the CRTC driving the connector.
+    pub fn crtc(&self) -> crtc::Handle {
+        self.crtc
+    }
+
+    /// Returns the handle to the primary plane of the CRTC, or [`None`] if
+    /// primary planes are not exposed.
+    pub fn plane(&self) -> Option<plane::Handle> {
+        self.plane
+    }
+
+    /// Returns the requested mode of the connector.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// A filter that can be used with a [`ResourceHandles`] to determine the set of
 /// Crtcs that can attach to a specific encoder.
+///
+/// Bit `n` refers to the `n`th CRTC of [`ResourceHandles::crtcs`], as
+/// returned on the file descriptor the filter was read from. See
+/// [`ResourceHandles::filter_crtcs`].
 pub struct CrtcListFilter(u32);
 
+impl CrtcListFilter {
+    /// Returns the raw bitmask of this filter.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether the CRTC at `index` in [`ResourceHandles::crtcs`] is
+    /// allowed by this filter.
+    pub fn contains_index(&self, index: usize) -> bool {
+        index < 32 && self.0 & (1 << index) != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A filter that can be used with a [`ResourceHandles`] to determine the set of
+/// encoders that can clone a specific encoder.
+///
+/// Bit `n` refers to the `n`th encoder of [`ResourceHandles::encoders`], as
+/// returned on the file descriptor the filter was read from. See
+/// [`ResourceHandles::filter_encoders`].
+pub struct EncoderListFilter(u32);
+
+impl EncoderListFilter {
+    /// Returns the raw bitmask of this filter.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether the encoder at `index` in [`ResourceHandles::encoders`]
+    /// is allowed by this filter.
+    pub fn contains_index(&self, index: usize) -> bool {
+        index < 32 && self.0 & (1 << index) != 0
+    }
+}
+
 /// Resolution and timing information for a display mode.
+///
+/// `==` compares every field, including the name and the rounded refresh
+/// rate. Use [`Mode::timing_eq`] to check whether two modes drive the display
+/// identically.
 #[repr(transparent)]
 #[derive(Copy, Clone, Hash, PartialEq, Eq, bytemuck::TransparentWrapper)]
 pub struct Mode {
@@ -1051,6 +3629,251 @@ impl Mode {
     pub fn flags(&self) -> ModeFlags {
         ModeFlags::from_bits_truncate(self.mode.flags)
     }
+
+    /// Sets the type flags of this mode
+    pub fn set_mode_type(&mut self, mode_type: ModeTypeFlags) {
+        self.mode.type_ = mode_type.bits();
+    }
+
+    /// Sets the flags of this mode
+    ///
+    /// The picture aspect ratio is kept, see [`Mode::set_picture_aspect`].
+    pub fn set_flags(&mut self, flags: ModeFlags) {
+        self.mode.flags = flags.bits() | (self.mode.flags & ffi::DRM_MODE_FLAG_PIC_AR_MASK);
+    }
+
+    /// Returns whether this mode is the preferred mode of its connector
+    pub fn is_preferred(&self) -> bool {
+        self.mode_type().contains(ModeTypeFlags::PREFERRED)
+    }
+
+    /// Returns whether this mode is interlaced
+    pub fn is_interlaced(&self) -> bool {
+        self.flags().contains(ModeFlags::INTERLACE)
+    }
+
+    /// Returns the picture aspect ratio of this mode
+    ///
+    /// The kernel only reports the aspect ratio to clients that enabled
+    /// [`ClientCapability::AspectRatio`](crate::ClientCapability::AspectRatio),
+    /// modes of other clients always return [`PictureAspectRatio::None`].
+    pub fn picture_aspect(&self) -> PictureAspectRatio {
+        match self.mode.flags & ffi::DRM_MODE_FLAG_PIC_AR_MASK {
+            ffi::DRM_MODE_FLAG_PIC_AR_4_3 => PictureAspectRatio::Aspect4_3,
+            ffi::DRM_MODE_FLAG_PIC_AR_16_9 => PictureAspectRatio::Aspect16_9,
+            ffi::DRM_MODE_FLAG_PIC_AR_64_27 => PictureAspectRatio::Aspect64_27,
+            ffi::DRM_MODE_FLAG_PIC_AR_256_135 => PictureAspectRatio::Aspect256_135,
+            _ => PictureAspectRatio::None,
+        }
+    }
+
+    /// Sets the picture aspect ratio of this mode
+    ///
+    /// The kernel only accepts modes with an aspect ratio from clients that
+    /// enabled [`ClientCapability::AspectRatio`](crate::ClientCapability::AspectRatio).
+    pub fn set_picture_aspect(&mut self, aspect: PictureAspectRatio) {
+        let bits = match aspect {
+            PictureAspectRatio::None => ffi::DRM_MODE_FLAG_PIC_AR_NONE,
+            PictureAspectRatio::Aspect4_3 => ffi::DRM_MODE_FLAG_PIC_AR_4_3,
+            PictureAspectRatio::Aspect16_9 => ffi::DRM_MODE_FLAG_PIC_AR_16_9,
+            PictureAspectRatio::Aspect64_27 => ffi::DRM_MODE_FLAG_PIC_AR_64_27,
+            PictureAspectRatio::Aspect256_135 => ffi::DRM_MODE_FLAG_PIC_AR_256_135,
+        };
+        self.mode.flags = (self.mode.flags & !ffi::DRM_MODE_FLAG_PIC_AR_MASK) | bits;
+    }
+
+    /// Returns whether two modes have the same timings.
+    ///
+    /// Compares the clock, the horizontal and vertical timings and the flags,
+    /// but ignores the name, the type, the refresh rate, which is only
+    /// derived from the timings, and the picture aspect ratio, which is only
+    /// reported to some clients. Useful to find a mode from another source,
+    /// e.g. a generated CVT mode, in the list of modes of a connector.
+    pub fn timing_eq(&self, other: &Mode) -> bool {
+        let (a, b) = (&self.mode, &other.mode);
+        let flags = |mode: &ffi::drm_mode_modeinfo| mode.flags & !ffi::DRM_MODE_FLAG_PIC_AR_MASK;
+
+        a.clock == b.clock
+            && (a.hdisplay, a.hsync_start, a.hsync_end, a.htotal, a.hskew)
+                == (b.hdisplay, b.hsync_start, b.hsync_end, b.htotal, b.hskew)
+            && (a.vdisplay, a.vsync_start, a.vsync_end, a.vtotal, a.vscan)
+                == (b.vdisplay, b.vsync_start, b.vsync_end, b.vtotal, b.vscan)
+            && flags(a) == flags(b)
+    }
+
+    /// Parses an X11 style modeline, e.g.
+    /// `"1920x1080" 148.5 1920 2008 2052 2200 1080 1084 1089 1125 +hsync +vsync`.
+    ///
+    /// The name may be quoted and the line may start with `Modeline`, as in
+    /// `xorg.conf`. The clock is given in MHz, followed by the horizontal and
+    /// vertical timings and optional flags: `+hsync`, `-hsync`, `+vsync`,
+    /// `-vsync`, `Interlace`, `DoubleScan`, `CSync`, `+CSync` and `-CSync`.
+    /// The parsed mode is marked as user defined, see [`ModeBuilder`].
+    ///
+    /// ```
+    /// use drm::control::Mode;
+    ///
+    /// let line = r#""1920x1080" 148.5 1920 2008 2052 2200 1080 1084 1089 1125 +hsync +vsync"#;
+    /// let mode = Mode::from_modeline(line).unwrap();
+    /// assert_eq!(mode.clock(), 148_500);
+    /// assert_eq!(mode.size(), (1920, 1080));
+    /// assert_eq!(mode.vrefresh(), 60);
+    /// assert_eq!(mode.to_modeline(), line);
+    ///
+    /// assert!(Mode::from_modeline("640x480 25.175 640 656 752 800 480").is_err());
+    /// ```
+    pub fn from_modeline(line: &str) -> Result<Mode, ModelineError> {
+        let mut line = line.trim();
+        if let Some(prefix) = line.get(..8) {
+            if prefix.eq_ignore_ascii_case("modeline") {
+                line = line[8..].trim_start();
+            }
+        }
+
+        let (name, rest) = match line.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').ok_or(ModelineError::Malformed)?,
+            None => line.split_once(char::is_whitespace).unwrap_or((line, "")),
+        };
+        let mut fields = rest.split_whitespace();
+
+        let clock = fields.next().ok_or(ModelineError::MissingField("clock"))?;
+        let clock = match clock.parse::<f64>() {
+            Ok(mhz) if mhz.is_finite() && mhz > 0.0 => (mhz * 1000.0).round() as u32,
+            _ => return Err(ModelineError::InvalidNumber(clock.to_owned())),
+        };
+
+        let mut timing = |field: &'static str| {
+            let value = fields.next().ok_or(ModelineError::MissingField(field))?;
+            value
+                .parse::<u16>()
+                .map_err(|_| ModelineError::InvalidNumber(value.to_owned()))
+        };
+        let hdisplay = timing("hdisplay")?;
+        let hsync = (
+            timing("hsync_start")?,
+            timing("hsync_end")?,
+            timing("htotal")?,
+        );
+        let vdisplay = timing("vdisplay")?;
+        let vsync = (
+            timing("vsync_start")?,
+            timing("vsync_end")?,
+            timing("vtotal")?,
+        );
+
+        let mut flags = ModeFlags::empty();
+        for flag in fields {
+            flags |= match flag.to_ascii_lowercase().as_str() {
+                "+hsync" => ModeFlags::PHSYNC,
+                "-hsync" => ModeFlags::NHSYNC,
+                "+vsync" => ModeFlags::PVSYNC,
+                "-vsync" => ModeFlags::NVSYNC,
+                "interlace" => ModeFlags::INTERLACE,
+                "doublescan" => ModeFlags::DBLSCAN,
+                "csync" => ModeFlags::CSYNC,
+                "+csync" => ModeFlags::PCSYNC,
+                "-csync" => ModeFlags::NCSYNC,
+                _ => return Err(ModelineError::UnknownFlag(flag.to_owned())),
+            };
+        }
+
+        ModeBuilder::new(clock, (hdisplay, vdisplay))
+            .hsync(hsync.0, hsync.1, hsync.2)
+            .vsync(vsync.0, vsync.1, vsync.2)
+            .flags(flags)
+            .name(name)
+            .build()
+            .map_err(|_| ModelineError::InvalidTimings)
+    }
+
+    /// Formats this mode as an X11 style modeline, the inverse of
+    /// [`Mode::from_modeline`].
+    pub fn to_modeline(&self) -> String {
+        let m = &self.mode;
+        let mut line = format!(
+            "\"{}\" {} {} {} {} {} {} {} {} {}",
+            self.name().to_string_lossy(),
+            f64::from(m.clock) / 1000.0,
+            m.hdisplay,
+            m.hsync_start,
+            m.hsync_end,
+            m.htotal,
+            m.vdisplay,
+            m.vsync_start,
+            m.vsync_end,
+            m.vtotal,
+        );
+
+        let flags = self.flags();
+        for &(flag, name) in &[
+            (ModeFlags::PHSYNC, "+hsync"),
+            (ModeFlags::NHSYNC, "-hsync"),
+            (ModeFlags::PVSYNC, "+vsync"),
+            (ModeFlags::NVSYNC, "-vsync"),
+            (ModeFlags::INTERLACE, "Interlace"),
+            (ModeFlags::DBLSCAN, "DoubleScan"),
+            (ModeFlags::CSYNC, "CSync"),
+            (ModeFlags::PCSYNC, "+CSync"),
+            (ModeFlags::NCSYNC, "-CSync"),
+        ] {
+            if flags.contains(flag) {
+                line.push(' ');
+                line.push_str(name);
+            }
+        }
+
+        line
+    }
+
+    /// Returns the horizontal display size of this mode.
+    pub fn hdisplay(&self) -> u16 {
+        self.mode.hdisplay
+    }
+
+    /// Returns the horizontal sync start of this mode.
+    pub fn hsync_start(&self) -> u16 {
+        self.mode.hsync_start
+    }
+
+    /// Returns the horizontal sync end of this mode.
+    pub fn hsync_end(&self) -> u16 {
+        self.mode.hsync_end
+    }
+
+    /// Returns the horizontal total size of this mode.
+    pub fn htotal(&self) -> u16 {
+        self.mode.htotal
+    }
+
+    /// Returns the vertical display size of this mode.
+    pub fn vdisplay(&self) -> u16 {
+        self.mode.vdisplay
+    }
+
+    /// Returns the vertical sync start of this mode.
+    pub fn vsync_start(&self) -> u16 {
+        self.mode.vsync_start
+    }
+
+    /// Returns the vertical sync end of this mode.
+    pub fn vsync_end(&self) -> u16 {
+        self.mode.vsync_end
+    }
+
+    /// Returns the vertical total size of this mode.
+    pub fn vtotal(&self) -> u16 {
+        self.mode.vtotal
+    }
+
+    /// Creates a mode from the raw kernel representation.
+    pub fn from_raw(raw: ffi::drm_mode_modeinfo) -> Mode {
+        Mode { mode: raw }
+    }
+
+    /// Returns the raw kernel representation of this mode.
+    pub fn as_raw(&self) -> ffi::drm_mode_modeinfo {
+        self.mode
+    }
 }
 
 impl From<ffi::drm_mode_modeinfo> for Mode {
@@ -1077,10 +3900,183 @@ impl std::fmt::Debug for Mode {
             .field("vscan", &self.vscan())
             .field("vrefresh", &self.vrefresh())
             .field("mode_type", &self.mode_type())
+            .field("flags", &self.flags())
             .finish()
     }
 }
 
+/// Error returned by [`Mode::from_modeline`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelineError {
+    /// The name is quoted, but the closing quote is missing
+    Malformed,
+    /// The line ends before the named field
+    MissingField(&'static str),
+    /// A field is not a valid number
+    InvalidNumber(String),
+    /// A flag is not known
+    UnknownFlag(String),
+    /// The timings are zero or not ordered, i.e.
+    /// `display <= sync start <= sync end <= total` does not hold
+    InvalidTimings,
+}
+
+impl std::fmt::Display for ModelineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ModelineError::Malformed => f.write_str("unterminated mode name"),
+            ModelineError::MissingField(field) => write!(f, "missing {}", field),
+            ModelineError::InvalidNumber(value) => write!(f, "invalid number {:?}", value),
+            ModelineError::UnknownFlag(flag) => write!(f, "unknown flag {:?}", flag),
+            ModelineError::InvalidTimings => f.write_str("timings are zero or not ordered"),
+        }
+    }
+}
+
+impl std::error::Error for ModelineError {}
+
+/// Builder for user defined modes with explicit timings
+///
+/// Useful to drive custom resolutions, e.g. on panels that do not report all
+/// modes they support. The built mode is marked with
+/// [`ModeTypeFlags::USERDEF`] and can be passed to [`Device::set_crtc`] or
+/// stored in a property blob.
+#[derive(Debug, Clone, Copy)]
+pub struct ModeBuilder {
+    mode: ffi::drm_mode_modeinfo,
+    vrefresh: Option<u32>,
+}
+
+impl ModeBuilder {
+    /// Starts building a mode with a pixel clock in kHz and a visible size.
+    ///
+    /// The sync pulses default to the end of the visible area with no
+    /// blanking, and have to be set with [`ModeBuilder::hsync`] and
+    /// [`ModeBuilder::vsync`] for the mode to be usable.
+    pub fn new(clock: u32, size: (u16, u16)) -> ModeBuilder {
+        let (width, height) = size;
+        let mode = ffi::drm_mode_modeinfo {
+            clock,
+            hdisplay: width,
+            hsync_start: width,
+            hsync_end: width,
+            htotal: width,
+            vdisplay: height,
+            vsync_start: height,
+            vsync_end: height,
+            vtotal: height,
+            type_: ffi::DRM_MODE_TYPE_USERDEF,
+            ..Default::default()
+        };
+
+        ModeBuilder {
+            mode,
+            vrefresh: None,
+        }
+    }
+
+    /// Sets the horizontal sync start, end and total.
+    pub fn hsync(mut self, start: u16, end: u16, total: u16) -> Self {
+        self.mode.hsync_start = start;
+        self.mode.hsync_end = end;
+        self.mode.htotal = total;
+        self
+    }
+
+    /// Sets the vertical sync start, end and total.
+    pub fn vsync(mut self, start: u16, end: u16, total: u16) -> Self {
+        self.mode.vsync_start = start;
+        self.mode.vsync_end = end;
+        self.mode.vtotal = total;
+        self
+    }
+
+    /// Sets the flags of the mode, e.g. the sync polarities.
+    pub fn flags(mut self, flags: ModeFlags) -> Self {
+        self.mode.flags = flags.bits();
+        self
+    }
+
+    /// Overrides the refresh rate reported by the mode.
+    ///
+    /// By default, it is derived from the clock and the totals. The kernel
+    /// ignores this value, it only affects [`Mode::vrefresh`].
+    pub fn vrefresh(mut self, vrefresh: u32) -> Self {
+        self.vrefresh = Some(vrefresh);
+        self
+    }
+
+    /// Sets the name of the mode, which defaults to `<width>x<height>`.
+    ///
+    /// Names longer than the kernel's limit are truncated.
+    pub fn name(mut self, name: &str) -> Self {
+        self.set_name(name);
+        self
+    }
+
+    fn set_name(&mut self, name: &str) {
+        // Keep the terminating zero
+        let len = name.len().min(self.mode.name.len() - 1);
+        self.mode.name = [0; ffi::DRM_DISPLAY_MODE_LEN as usize];
+        for (dst, &src) in self.mode.name.iter_mut().zip(&name.as_bytes()[..len]) {
+            *dst = src as _;
+        }
+    }
+
+    /// Builds the mode.
+    ///
+    /// Returns [`SystemError::InvalidArgument`] if the clock is zero, or if
+    /// the sync pulses are not ordered within the blanking, i.e.
+    /// `display <= sync start <= sync end <= total` does not hold in either
+    /// direction.
+    pub fn build(mut self) -> Result<Mode, SystemError> {
+        let m = &self.mode;
+        let ordered = |display: u16, start: u16, end: u16, total: u16| {
+            display > 0 && display <= start && start <= end && end <= total
+        };
+        if m.clock == 0
+            || !ordered(m.hdisplay, m.hsync_start, m.hsync_end, m.htotal)
+            || !ordered(m.vdisplay, m.vsync_start, m.vsync_end, m.vtotal)
+        {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        if self.mode.name[0] == 0 {
+            let name = format!("{}x{}", m.hdisplay, m.vdisplay);
+            self.set_name(&name);
+        }
+
+        self.mode.vrefresh = match self.vrefresh {
+            Some(vrefresh) => vrefresh,
+            None => refresh_rate(&self.mode),
+        };
+
+        Ok(Mode { mode: self.mode })
+    }
+}
+
+/// Computes the refresh rate of a mode in Hz the way the kernel does, rounded
+/// to the nearest integer.
+fn refresh_rate(mode: &ffi::drm_mode_modeinfo) -> u32 {
+    let mut frame = u64::from(mode.htotal) * u64::from(mode.vtotal);
+    if frame == 0 {
+        return 0;
+    }
+    if mode.flags & ffi::DRM_MODE_FLAG_DBLSCAN != 0 {
+        frame *= 2;
+    }
+    if mode.vscan > 1 {
+        frame *= u64::from(mode.vscan);
+    }
+
+    // Interlaced modes show two fields per frame, doubled before rounding
+    let mut clock = u64::from(mode.clock) * 1000;
+    if mode.flags & ffi::DRM_MODE_FLAG_INTERLACE != 0 {
+        clock *= 2;
+    }
+    ((clock + frame / 2) / frame) as u32
+}
+
 bitflags::bitflags! {
     /// Display mode type flags
     pub struct ModeTypeFlags : u32 {
@@ -1156,9 +4152,52 @@ bitflags::bitflags! {
         const _3D_TOP_AND_BOTTOM = ffi::DRM_MODE_FLAG_3D_TOP_AND_BOTTOM;
         /// Stereo 3D mode utilizing side by side half size image
         const _3D_SIDE_BY_SIDE_HALF = ffi::DRM_MODE_FLAG_3D_SIDE_BY_SIDE_HALF;
+        /// Bitmask of all valid mode flags, excluding the picture aspect ratio
+        const ALL = ffi::DRM_MODE_FLAG_ALL;
+    }
+}
+
+impl From<ModeFlags> for u32 {
+    fn from(flags: ModeFlags) -> Self {
+        flags.bits()
+    }
+}
+
+impl From<u32> for ModeFlags {
+    /// Unknown bits are discarded.
+    fn from(bits: u32) -> Self {
+        ModeFlags::from_bits_truncate(bits)
+    }
+}
+
+impl From<ModeTypeFlags> for u32 {
+    fn from(flags: ModeTypeFlags) -> Self {
+        flags.bits()
+    }
+}
+
+impl From<u32> for ModeTypeFlags {
+    /// Unknown bits are discarded.
+    fn from(bits: u32) -> Self {
+        ModeTypeFlags::from_bits_truncate(bits)
     }
 }
 
+/// Picture aspect ratio of a mode, sent to HDMI displays in the AVI infoframe
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum PictureAspectRatio {
+    /// No aspect ratio is specified
+    None,
+    /// 4:3
+    Aspect4_3,
+    /// 16:9
+    Aspect16_9,
+    /// 64:27
+    Aspect64_27,
+    /// 256:135
+    Aspect256_135,
+}
+
 /// Type of a plane
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1171,8 +4210,57 @@ pub enum PlaneType {
     Cursor = ffi::DRM_PLANE_TYPE_CURSOR,
 }
 
+/// Type of a modesetting object
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectType {
+    /// CRTC object
+    Crtc = ffi::DRM_MODE_OBJECT_CRTC,
+    /// Connector object
+    Connector = ffi::DRM_MODE_OBJECT_CONNECTOR,
+    /// Encoder object
+    Encoder = ffi::DRM_MODE_OBJECT_ENCODER,
+    /// Mode object
+    Mode = ffi::DRM_MODE_OBJECT_MODE,
+    /// Property object
+    Property = ffi::DRM_MODE_OBJECT_PROPERTY,
+    /// Framebuffer object
+    Framebuffer = ffi::DRM_MODE_OBJECT_FB,
+    /// Property blob object
+    Blob = ffi::DRM_MODE_OBJECT_BLOB,
+    /// Plane object
+    Plane = ffi::DRM_MODE_OBJECT_PLANE,
+    /// Wildcard matching objects of any type
+    Any = ffi::DRM_MODE_OBJECT_ANY,
+}
+
+impl From<ObjectType> for u32 {
+    fn from(object_type: ObjectType) -> Self {
+        object_type as u32
+    }
+}
+
+impl TryFrom<u32> for ObjectType {
+    type Error = SystemError;
+
+    fn try_from(raw: u32) -> Result<Self, Self::Error> {
+        match raw {
+            ffi::DRM_MODE_OBJECT_CRTC => Ok(ObjectType::Crtc),
+            ffi::DRM_MODE_OBJECT_CONNECTOR => Ok(ObjectType::Connector),
+            ffi::DRM_MODE_OBJECT_ENCODER => Ok(ObjectType::Encoder),
+            ffi::DRM_MODE_OBJECT_MODE => Ok(ObjectType::Mode),
+            ffi::DRM_MODE_OBJECT_PROPERTY => Ok(ObjectType::Property),
+            ffi::DRM_MODE_OBJECT_FB => Ok(ObjectType::Framebuffer),
+            ffi::DRM_MODE_OBJECT_BLOB => Ok(ObjectType::Blob),
+            ffi::DRM_MODE_OBJECT_PLANE => Ok(ObjectType::Plane),
+            ffi::DRM_MODE_OBJECT_ANY => Ok(ObjectType::Any),
+            _ => Err(SystemError::InvalidArgument),
+        }
+    }
+}
+
 /// Wrapper around a set of property IDs and their raw values.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct PropertyValueSet {
     prop_ids: Vec<property::Handle>,
     prop_vals: Vec<property::RawValue>,
@@ -1211,7 +4299,47 @@ impl IntoIterator for PropertyValueSet {
 }
 
 /// Describes a rectangular region of a buffer
-pub type ClipRect = ffi::drm_sys::drm_clip_rect;
+///
+/// The region spans from `(x1, y1)` inclusive to `(x2, y2)` exclusive.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct ClipRect {
+    /// Left edge
+    pub x1: u16,
+    /// Top edge
+    pub y1: u16,
+    /// Right edge
+    pub x2: u16,
+    /// Bottom edge
+    pub y2: u16,
+}
+
+impl ClipRect {
+    /// Creates a region from its top left and bottom right corner.
+    pub fn new(x1: u16, y1: u16, x2: u16, y2: u16) -> ClipRect {
+        ClipRect { x1, y1, x2, y2 }
+    }
+}
+
+impl From<ClipRect> for ffi::drm_clip_rect {
+    fn from(clip: ClipRect) -> Self {
+        ffi::drm_clip_rect {
+            x1: clip.x1,
+            y1: clip.y1,
+            x2: clip.x2,
+            y2: clip.y2,
+        }
+    }
+}
+
+/// Hint about how the dirty regions of a framebuffer changed, see
+/// [`Device::dirty_framebuffer_annotated`]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum DirtyAnnotation {
+    /// The regions were copied from another part of the framebuffer
+    Copy,
+    /// The regions were filled with a single color
+    Fill(u32),
+}
 
 bitflags::bitflags! {
     /// Commit flags for atomic mode setting
@@ -1221,6 +4349,12 @@ bitflags::bitflags! {
         /// Generate a page flip event, when the changes are applied
         const PAGE_FLIP_EVENT = ffi::drm_sys::DRM_MODE_PAGE_FLIP_EVENT;
         /// Request page flip when the changes are applied, not waiting for vblank
+        ///
+        /// Asynchronous atomic commits may only change the `FB_ID` of planes
+        /// and cannot be combined with [`AtomicCommitFlags::ALLOW_MODESET`],
+        /// see [`atomic::AtomicModeReq::is_async_compatible`]. Not all drivers
+        /// support them yet, check
+        /// [`DriverCapability::AtomicASyncPageFlip`](crate::DriverCapability::AtomicASyncPageFlip).
         const PAGE_FLIP_ASYNC = ffi::drm_sys::DRM_MODE_PAGE_FLIP_ASYNC;
         /// Test only validity of the request, do not actually apply the requested changes
         const TEST_ONLY = ffi::drm_sys::DRM_MODE_ATOMIC_TEST_ONLY;
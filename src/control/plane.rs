@@ -17,6 +17,12 @@
 
 use control;
 use drm_ffi as ffi;
+use drm_ffi::result::SystemError;
+use drm_fourcc::{DrmFourcc, DrmModifier};
+
+use std::collections::HashMap;
+use std::os::unix::io::AsRawFd;
+use util::SharedMap;
 
 /// A handle to a plane
 #[repr(transparent)]
@@ -62,7 +68,7 @@ pub struct Info {
     pub(crate) crtc: Option<control::crtc::Handle>,
     pub(crate) fb: Option<control::framebuffer::Handle>,
     pub(crate) pos_crtcs: u32,
-    pub(crate) formats: Vec<u32>,
+    pub(crate) formats: Vec<DrmFourcc>,
 }
 
 impl Info {
@@ -71,6 +77,22 @@ impl Info {
         self.handle
     }
 
+    /// Returns the properties of this plane along with their decoded values,
+    /// keyed by property name.
+    ///
+    /// Use [`control::Device::get_named_properties`] with a shared
+    /// [`control::property::InfoCache`] when reading the properties of
+    /// several objects.
+    pub fn properties<D>(
+        &self,
+        card: &D,
+    ) -> Result<HashMap<String, control::dump::ValueDump>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        card.get_named_properties(self.handle, &control::property::InfoCache::new(card))
+    }
+
     /// Returns the CRTC this plane is attached to.
     pub fn crtc(&self) -> Option<control::crtc::Handle> {
         self.crtc
@@ -84,13 +106,510 @@ impl Info {
         control::CrtcListFilter(self.pos_crtcs)
     }
 
+    /// Returns the CRTCs of `resources` this plane can be attached to.
+    ///
+    /// Shorthand for filtering the CRTCs with [`Info::possible_crtcs`].
+    pub fn supported_crtcs(
+        &self,
+        resources: &control::ResourceHandles,
+    ) -> Vec<control::crtc::Handle> {
+        resources.filter_crtcs(self.possible_crtcs())
+    }
+
     /// Returns the framebuffer this plane is attached to.
     pub fn framebuffer(&self) -> Option<control::framebuffer::Handle> {
         self.fb
     }
 
     /// Returns the formats this plane supports.
-    pub fn formats(&self) -> &[u32] {
+    ///
+    /// Formats unknown to [`DrmFourcc`] are left out.
+    pub fn formats(&self) -> &[DrmFourcc] {
         &self.formats
     }
+
+    /// Returns whether this plane can scan out framebuffers of a format.
+    ///
+    /// This only checks the format list of the plane, which makes it a cheap
+    /// filter before testing an actual configuration with an atomic commit.
+    pub fn supports_format(&self, format: DrmFourcc) -> bool {
+        self.formats.contains(&format)
+    }
+
+    /// Returns the formats this plane supports along with the modifiers
+    /// supported for each format, read from the `IN_FORMATS` property.
+    ///
+    /// If the driver does not expose the property, every format of
+    /// [`Info::formats`] is returned with [`DrmModifier::Invalid`], meaning
+    /// the driver picks the layout implicitly.
+    pub fn format_modifiers<D>(&self, card: &D) -> Result<Vec<(u32, Vec<DrmModifier>)>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let blob = match control::find_property(card, self.handle, &["IN_FORMATS"])?
+            .and_then(|(_, blob)| control::from_u32(blob as u32))
+        {
+            Some(blob) => blob,
+            None => {
+                return Ok(self
+                    .formats
+                    .iter()
+                    .map(|&format| (format as u32, vec![DrmModifier::Invalid]))
+                    .collect())
+            }
+        };
+
+        let data = card.get_property_blob(blob)?;
+        parse_in_formats(&data).ok_or(SystemError::InvalidArgument)
+    }
+
+    /// Returns the source and destination rectangles of this plane.
+    ///
+    /// Returns [`None`] if no framebuffer is attached to the plane. The
+    /// rectangles are read from the atomic plane properties, so
+    /// [`SystemError::Unsupported`] is returned if the atomic client
+    /// capability is not enabled.
+    pub fn current_geometry<D>(&self, card: &D) -> Result<Option<PlaneGeometry>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        if self.fb.is_none() {
+            return Ok(None);
+        }
+
+        let names = [
+            "SRC_X", "SRC_Y", "SRC_W", "SRC_H", "CRTC_X", "CRTC_Y", "CRTC_W", "CRTC_H",
+        ];
+        let mut values = [None; 8];
+        for (id, value) in card.get_properties(self.handle)? {
+            let info = card.get_property(id)?;
+            if let Some(i) = names
+                .iter()
+                .position(|&name| info.name().to_bytes() == name.as_bytes())
+            {
+                values[i] = Some(value);
+            }
+        }
+
+        let mut raw = [0; 8];
+        for (raw, value) in raw.iter_mut().zip(&values) {
+            *raw = value.ok_or(SystemError::Unsupported)?;
+        }
+
+        let fixed = |value: u64| value as f64 / 65536.0;
+        Ok(Some(PlaneGeometry {
+            src: (fixed(raw[0]), fixed(raw[1]), fixed(raw[2]), fixed(raw[3])),
+            dst: (raw[4] as i32, raw[5] as i32, raw[6] as u32, raw[7] as u32),
+        }))
+    }
+
+    /// Returns the rotations and reflections this plane supports.
+    ///
+    /// Planes without a `rotation` property can only be displayed
+    /// unrotated, and return [`Rotation::ROTATE_0`].
+    pub fn supported_rotations<D>(&self, card: &D) -> Result<Rotation, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        Ok(rotation_property(card, self.handle)?
+            .map_or(Rotation::ROTATE_0, |(_, supported)| supported))
+    }
+
+    /// Returns the sizes of framebuffers the driver recommends for this
+    /// plane, as `(width, height)` pairs in order of preference.
+    ///
+    /// Read from the `SIZE_HINTS` property, which drivers restricting the
+    /// sizes of cursor planes expose. If a cursor plane lacks the property,
+    /// the size reported by [`DriverCapability::CursorWidth`] and
+    /// [`DriverCapability::CursorHeight`] is returned instead. An empty list
+    /// means there are no hints.
+    ///
+    /// [`DriverCapability::CursorWidth`]: crate::DriverCapability::CursorWidth
+    /// [`DriverCapability::CursorHeight`]: crate::DriverCapability::CursorHeight
+    pub fn size_hints<D>(&self, card: &D) -> Result<Vec<(u16, u16)>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        match control::find_property(card, self.handle, &["SIZE_HINTS"])?
+            .map(|(_, blob)| control::from_u32(blob as u32))
+        {
+            Some(None) => Ok(Vec::new()),
+            Some(Some(blob)) => {
+                let data = card.get_property_blob(blob)?;
+                let hints = data
+                    .chunks_exact(4)
+                    .map(|hint| {
+                        (
+                            u16::from_ne_bytes([hint[0], hint[1]]),
+                            u16::from_ne_bytes([hint[2], hint[3]]),
+                        )
+                    })
+                    .collect();
+                Ok(hints)
+            }
+            None => {
+                if control::plane_type(card, self.handle)? != Some(control::PlaneType::Cursor) {
+                    return Ok(Vec::new());
+                }
+
+                let width = card.get_driver_capability(crate::DriverCapability::CursorWidth);
+                let height = card.get_driver_capability(crate::DriverCapability::CursorHeight);
+                match (width, height) {
+                    (Ok(width), Ok(height)) => Ok(vec![(width as u16, height as u16)]),
+                    _ => Ok(Vec::new()),
+                }
+            }
+        }
+    }
+}
+
+/// Decodes an `IN_FORMATS` blob, returning [`None`] if it is malformed.
+fn parse_in_formats(data: &[u8]) -> Option<Vec<(u32, Vec<DrmModifier>)>> {
+    fn read<T>(data: &[u8], offset: usize) -> Option<T> {
+        if data.len() < offset.checked_add(std::mem::size_of::<T>())? {
+            return None;
+        }
+        Some(unsafe { std::ptr::read_unaligned(data.as_ptr().add(offset) as *const T) })
+    }
+
+    let header: ffi::drm_format_modifier_blob = read(data, 0)?;
+
+    let mut formats = Vec::with_capacity(header.count_formats as usize);
+    for i in 0..header.count_formats as usize {
+        let format: u32 = read(data, header.formats_offset as usize + i * 4)?;
+        formats.push((format, Vec::new()));
+    }
+
+    let size = std::mem::size_of::<ffi::drm_format_modifier>();
+    for i in 0..header.count_modifiers as usize {
+        let modifier: ffi::drm_format_modifier =
+            read(data, header.modifiers_offset as usize + i * size)?;
+        // Bit n of the mask refers to the format at index `offset + n`
+        for bit in 0..64 {
+            if modifier.formats & (1 << bit) != 0 {
+                let (_, modifiers) = formats.get_mut(modifier.offset as usize + bit)?;
+                modifiers.push(DrmModifier::from(modifier.modifier));
+            }
+        }
+    }
+
+    Some(formats)
+}
+
+/// Formats and modifiers supported for scanout, grouped by plane type
+///
+/// Returned by [`control::Device::format_support`]. Formats unknown to
+/// [`DrmFourcc`] are left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatSupport {
+    pub(crate) primary: Vec<(DrmFourcc, Vec<DrmModifier>)>,
+    pub(crate) overlay: Vec<(DrmFourcc, Vec<DrmModifier>)>,
+    pub(crate) cursor: Vec<(DrmFourcc, Vec<DrmModifier>)>,
+}
+
+impl FormatSupport {
+    /// Returns the formats supported by any plane of the given type, along
+    /// with the modifiers supported for each format by at least one of
+    /// those planes.
+    pub fn for_type(&self, plane_type: control::PlaneType) -> &[(DrmFourcc, Vec<DrmModifier>)] {
+        match plane_type {
+            control::PlaneType::Primary => &self.primary,
+            control::PlaneType::Overlay => &self.overlay,
+            control::PlaneType::Cursor => &self.cursor,
+        }
+    }
+
+    /// Returns the modifiers supported for a format by planes of the given
+    /// type. The list is empty if the format is not supported.
+    pub fn modifiers(&self, plane_type: control::PlaneType, format: DrmFourcc) -> &[DrmModifier] {
+        self.for_type(plane_type)
+            .iter()
+            .find(|(known, _)| *known == format)
+            .map_or(&[], |(_, modifiers)| modifiers)
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        plane_type: control::PlaneType,
+        format: DrmFourcc,
+        modifiers: &[DrmModifier],
+    ) {
+        let formats = match plane_type {
+            control::PlaneType::Primary => &mut self.primary,
+            control::PlaneType::Overlay => &mut self.overlay,
+            control::PlaneType::Cursor => &mut self.cursor,
+        };
+
+        let index = match formats.iter().position(|(known, _)| *known == format) {
+            Some(index) => index,
+            None => {
+                formats.push((format, Vec::new()));
+                formats.len() - 1
+            }
+        };
+
+        let known = &mut formats[index].1;
+        for &modifier in modifiers {
+            if !known.contains(&modifier) {
+                known.push(modifier);
+            }
+        }
+    }
+}
+
+/// The placement of a framebuffer on a CRTC by a plane
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlaneGeometry {
+    pub(crate) src: (f64, f64, f64, f64),
+    pub(crate) dst: (i32, i32, u32, u32),
+}
+
+impl PlaneGeometry {
+    /// Returns the position and size of the displayed region of the
+    /// framebuffer, as `(x, y, width, height)` in pixels.
+    pub fn src(&self) -> (f64, f64, f64, f64) {
+        self.src
+    }
+
+    /// Returns the position and size of the plane on the CRTC, as
+    /// `(x, y, width, height)` in pixels. The position can be negative if the
+    /// plane extends beyond the top left corner of the CRTC.
+    pub fn dst(&self) -> (i32, i32, u32, u32) {
+        self.dst
+    }
+}
+
+/// The current state of a plane bound to a CRTC
+///
+/// Returned by [`control::Device::current_plane_assignments`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlaneAssignment {
+    pub(crate) plane: Handle,
+    pub(crate) plane_type: Option<control::PlaneType>,
+    pub(crate) crtc: control::crtc::Handle,
+    pub(crate) framebuffer: control::framebuffer::Handle,
+    pub(crate) geometry: PlaneGeometry,
+    pub(crate) zpos: Option<u64>,
+}
+
+impl PlaneAssignment {
+    /// Returns the handle to the plane.
+    pub fn plane(&self) -> Handle {
+        self.plane
+    }
+
+    /// Returns the type of the plane, or [`None`] if the plane does not
+    /// expose it.
+    pub fn plane_type(&self) -> Option<control::PlaneType> {
+        self.plane_type
+    }
+
+    /// Returns the CRTC the plane is bound to.
+    pub fn crtc(&self) -> control::crtc::Handle {
+        self.crtc
+    }
+
+    /// Returns the framebuffer displayed by the plane.
+    pub fn framebuffer(&self) -> control::framebuffer::Handle {
+        self.framebuffer
+    }
+
+    /// Returns the source and destination rectangles of the plane.
+    pub fn geometry(&self) -> PlaneGeometry {
+        self.geometry
+    }
+
+    /// Returns the `zpos` of the plane, or [`None`] if the plane does not
+    /// expose it. Planes with a higher `zpos` are stacked on top.
+    pub fn zpos(&self) -> Option<u64> {
+        self.zpos
+    }
+}
+
+/// The properties of a plane managed by
+/// [`AtomicModeReq::set_plane_state`](control::atomic::AtomicModeReq::set_plane_state)
+#[derive(Debug, Default, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct PlaneState {
+    /// The CRTC the plane is bound to, `CRTC_ID`
+    pub crtc: Option<control::crtc::Handle>,
+    /// The framebuffer displayed by the plane, `FB_ID`
+    pub framebuffer: Option<control::framebuffer::Handle>,
+    /// The displayed region of the framebuffer as `(x, y, width, height)` in
+    /// 16.16 fixed point, `SRC_*`
+    pub src: (u32, u32, u32, u32),
+    /// The position and size of the plane on the CRTC as
+    /// `(x, y, width, height)` in pixels, `CRTC_*`
+    pub dst: (i32, i32, u32, u32),
+}
+
+impl PlaneState {
+    const PROPERTY_NAMES: [&'static str; 10] = [
+        "CRTC_ID", "FB_ID", "SRC_X", "SRC_Y", "SRC_W", "SRC_H", "CRTC_X", "CRTC_Y", "CRTC_W",
+        "CRTC_H",
+    ];
+
+    fn values(&self) -> [control::property::RawValue; 10] {
+        let (sx, sy, sw, sh) = self.src;
+        let (dx, dy, dw, dh) = self.dst;
+        [
+            self.crtc.map_or(0, |crtc| u32::from(crtc).into()),
+            self.framebuffer.map_or(0, |fb| u32::from(fb).into()),
+            sx.into(),
+            sy.into(),
+            sw.into(),
+            sh.into(),
+            // Signed range properties take the two's complement
+            dx as i64 as u64,
+            dy as i64 as u64,
+            dw.into(),
+            dh.into(),
+        ]
+    }
+}
+
+/// The last committed [`PlaneState`] of every plane
+///
+/// Used by
+/// [`AtomicModeReq::set_plane_state`](control::atomic::AtomicModeReq::set_plane_state)
+/// to only add the properties that changed. After a request has been
+/// committed successfully, pass it to [`PlaneStateCache::update`]. The cache
+/// cannot tell about commits made without it, call
+/// [`PlaneStateCache::invalidate`] after those, so that every property is
+/// written again. The cache can be shared between threads.
+#[derive(Debug, Default)]
+pub struct PlaneStateCache {
+    props: SharedMap<Handle, [control::property::Handle; 10]>,
+    committed: SharedMap<Handle, PlaneState>,
+}
+
+impl PlaneStateCache {
+    /// Creates an empty cache.
+    pub fn new() -> PlaneStateCache {
+        Self::default()
+    }
+
+    /// Records the plane states of a successfully committed request.
+    pub fn update(&self, req: &control::atomic::AtomicModeReq) {
+        self.committed
+            .lock()
+            .extend(req.plane_states.iter().copied());
+    }
+
+    /// Forgets the committed state of all planes, e.g. after committing a
+    /// request that was not built with the cache.
+    pub fn invalidate(&self) {
+        self.committed.lock().clear();
+    }
+
+    /// Forgets the committed state of a plane.
+    pub fn invalidate_plane(&self, plane: Handle) {
+        self.committed.lock().remove(&plane);
+    }
+
+    /// Returns the values to write for a plane to reach the desired state,
+    /// skipping those already committed.
+    pub(crate) fn changes<D>(
+        &self,
+        card: &D,
+        plane: Handle,
+        desired: &PlaneState,
+    ) -> Result<Vec<(control::property::Handle, control::property::RawValue)>, SystemError>
+    where
+        D: control::Device + ?Sized,
+    {
+        let props = self
+            .props
+            .get_or_try_insert_with(plane, || plane_state_properties(card, plane))?;
+
+        let committed = self.committed.lock().get(&plane).map(PlaneState::values);
+        let changes = props
+            .iter()
+            .zip(&desired.values())
+            .enumerate()
+            .filter(|&(i, (_, &value))| committed.map_or(true, |values| values[i] != value))
+            .map(|(_, (&prop, &value))| (prop, value))
+            .collect();
+
+        Ok(changes)
+    }
+}
+
+fn plane_state_properties<D>(
+    card: &D,
+    plane: Handle,
+) -> Result<[control::property::Handle; 10], SystemError>
+where
+    D: control::Device + ?Sized,
+{
+    let mut props = [None; 10];
+    for (&id, _) in card.get_properties(plane)?.iter() {
+        let info = card.get_property(id)?;
+        if let Some(i) = PlaneState::PROPERTY_NAMES
+            .iter()
+            .position(|&name| info.name().to_bytes() == name.as_bytes())
+        {
+            props[i] = Some(id);
+        }
+    }
+
+    if props.iter().any(Option::is_none) {
+        return Err(SystemError::Unsupported);
+    }
+    Ok(props.map(Option::unwrap))
+}
+
+bitflags::bitflags! {
+    /// Rotation and reflection of a plane, set through its `rotation`
+    /// property
+    ///
+    /// The rotation is counter-clockwise, a value should contain exactly one
+    /// of the `ROTATE_*` flags.
+    pub struct Rotation: u32 {
+        /// No rotation
+        const ROTATE_0 = ffi::DRM_MODE_ROTATE_0;
+        /// Rotation by 90 degrees
+        const ROTATE_90 = ffi::DRM_MODE_ROTATE_90;
+        /// Rotation by 180 degrees
+        const ROTATE_180 = ffi::DRM_MODE_ROTATE_180;
+        /// Rotation by 270 degrees
+        const ROTATE_270 = ffi::DRM_MODE_ROTATE_270;
+        /// Reflection along the X axis
+        const REFLECT_X = ffi::DRM_MODE_REFLECT_X;
+        /// Reflection along the Y axis
+        const REFLECT_Y = ffi::DRM_MODE_REFLECT_Y;
+    }
+}
+
+/// Looks up the `rotation` property of a plane, returning its handle and the
+/// supported values.
+///
+/// [`control::property::ValueType::Bitmask`] does not carry the entries of
+/// the bitmask, so they are queried directly.
+pub(crate) fn rotation_property<D>(
+    card: &D,
+    handle: Handle,
+) -> Result<Option<(control::property::Handle, Rotation)>, SystemError>
+where
+    D: control::Device + ?Sized,
+{
+    let (info, _) = match control::find_property(card, handle, &["rotation"])? {
+        Some(prop) => prop,
+        None => return Ok(None),
+    };
+
+    let mut entries = Vec::new();
+    ffi::mode::get_property(
+        card.as_fd().as_raw_fd(),
+        info.handle().into(),
+        None,
+        Some(&mut entries),
+    )?;
+
+    // Entries of bitmask properties hold the index of their bit
+    let bits = entries
+        .iter()
+        .filter(|entry| entry.value < 32)
+        .fold(0, |bits, entry| bits | 1 << entry.value);
+
+    Ok(Some((info.handle(), Rotation::from_bits_truncate(bits))))
 }
@@ -11,8 +11,11 @@
 //! directly changing the property value itself, or by batching property changes
 //! together and executing them all atomically.
 
-use control::{RawResourceHandle, ResourceHandle};
+use control::{self, RawResourceHandle, ResourceHandle};
 use drm_ffi as ffi;
+use drm_ffi::result::SystemError;
+use std::collections::HashMap;
+use util::SharedMap;
 
 /// A raw property value that does not have a specific property type
 pub type RawValue = u64;
@@ -91,6 +94,106 @@ impl Info {
     }
 }
 
+/// A cache of the property information of a device, keyed by property
+/// handle
+///
+/// The metadata of a property does not change while the device is open, so
+/// a cache can be shared when reading the properties of several objects, see
+/// [`control::Device::get_named_properties`]. Property handles are only
+/// meaningful for the device they were queried from, so the cache borrows
+/// that device. The cache can be shared between threads.
+pub struct InfoCache<'a, D: ?Sized> {
+    card: &'a D,
+    infos: SharedMap<Handle, Info>,
+}
+
+impl<'a, D: control::Device + ?Sized> InfoCache<'a, D> {
+    /// Creates an empty cache for a device.
+    pub fn new(card: &'a D) -> InfoCache<'a, D> {
+        InfoCache {
+            card,
+            infos: SharedMap::default(),
+        }
+    }
+
+    /// Returns the device this cache belongs to.
+    pub fn card(&self) -> &'a D {
+        self.card
+    }
+
+    /// Returns the information about a property, querying the device only
+    /// on the first lookup.
+    pub fn get(&self, handle: Handle) -> Result<Info, SystemError> {
+        self.infos
+            .get_or_try_insert_with(handle, || self.card.get_property(handle))
+    }
+}
+
+impl<'a, D: ?Sized> std::fmt::Debug for InfoCache<'a, D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("InfoCache")
+            .field("infos", &self.infos)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The properties of all connectors, CRTCs and planes of a device, indexed
+/// by object and property name
+///
+/// Returned by [`control::Device::property_table`]. The set of properties of
+/// an object does not change while the device is open, so a table can be
+/// built once and used to resolve property names without further ioctls,
+/// e.g. by [`AtomicModeReq::set_named_property`](control::atomic::AtomicModeReq::set_named_property).
+/// Connectors appearing later, e.g. on a DisplayPort MST hub, are missing
+/// from the table until it is rebuilt.
+#[derive(Debug, Clone, Default)]
+pub struct PropertyTable {
+    pub(crate) objects: HashMap<RawResourceHandle, HashMap<String, Info>>,
+}
+
+impl PropertyTable {
+    /// Returns the information about a property of an object, or [`None`] if
+    /// the object does not have a property of this name.
+    pub fn get<T: ResourceHandle>(&self, object: T, name: &str) -> Option<&Info> {
+        self.objects.get(&object.into())?.get(name)
+    }
+
+    /// Returns the handle to a property of an object, or [`None`] if the
+    /// object does not have a property of this name.
+    pub fn handle<T: ResourceHandle>(&self, object: T, name: &str) -> Option<Handle> {
+        self.get(object, name).map(Info::handle)
+    }
+
+    /// Returns the properties of an object keyed by name, or [`None`] if the
+    /// object is not part of the table.
+    pub fn properties<T: ResourceHandle>(&self, object: T) -> Option<&HashMap<String, Info>> {
+        self.objects.get(&object.into())
+    }
+
+    pub(crate) fn insert<D, T>(
+        &mut self,
+        cache: &InfoCache<D>,
+        object: T,
+    ) -> Result<(), SystemError>
+    where
+        D: control::Device + ?Sized,
+        T: ResourceHandle,
+    {
+        let props = cache
+            .card()
+            .get_properties(object)?
+            .iter()
+            .map(|(&prop, _)| {
+                let info = cache.get(prop)?;
+                Ok((info.name().to_string_lossy().into_owned(), info))
+            })
+            .collect::<Result<_, SystemError>>()?;
+
+        self.objects.insert(object.into(), props);
+        Ok(())
+    }
+}
+
 /// Describes the types of value that a property uses.
 #[allow(clippy::upper_case_acronyms)]
 #[allow(clippy::large_enum_variant)]
@@ -136,7 +239,7 @@ impl ValueType {
             ValueType::SignedRange(_, _) => Value::SignedRange(value as i64),
             ValueType::Enum(values) => Value::Enum(values.get_value_from_raw_value(value)),
             ValueType::Bitmask => Value::Bitmask(value),
-            ValueType::Blob => Value::Blob(value),
+            ValueType::Blob => Value::Blob(bytemuck::cast(value as u32)),
             ValueType::Object => Value::Object(bytemuck::cast(value as u32)),
             ValueType::CRTC => Value::CRTC(bytemuck::cast(value as u32)),
             ValueType::Connector => Value::Connector(bytemuck::cast(value as u32)),
@@ -166,7 +269,7 @@ pub enum Value<'a> {
     /// Bitmask value
     Bitmask(u64),
     /// Opaque (blob) value
-    Blob(u64),
+    Blob(Option<super::blob::Handle>),
     /// Unknown object value
     Object(Option<RawResourceHandle>),
     /// Crtc object value
@@ -193,7 +296,7 @@ impl<'a> From<Value<'a>> for RawValue {
             Value::SignedRange(x) => x as u64,
             Value::Enum(val) => val.map_or(0, EnumValue::value),
             Value::Bitmask(x) => x,
-            Value::Blob(x) => x,
+            Value::Blob(x) => bytemuck::cast::<_, u32>(x) as u64,
             Value::Object(x) => bytemuck::cast::<_, u32>(x) as u64,
             Value::CRTC(x) => bytemuck::cast::<_, u32>(x) as u64,
             Value::Connector(x) => bytemuck::cast::<_, u32>(x) as u64,
@@ -242,8 +345,8 @@ impl<'a> Value<'a> {
     }
 
     /// Opaque (blob) value
-    pub fn as_blob(&self) -> Option<u64> {
-        match_variant!(self, Blob)
+    pub fn as_blob(&self) -> Option<super::blob::Handle> {
+        match_variant!(self, Blob).flatten()
     }
 
     /// Unknown object value
@@ -23,7 +23,8 @@
 //!
 //! To begin using this crate, the [`Device`] trait must be
 //! implemented. See the trait's [example section](trait@Device#example) for
-//! details on how to implement it.
+//! details on how to implement it. The [`node`] module provides helpers to
+//! open the device nodes to implement it for.
 //!
 
 #![warn(missing_docs)]
@@ -36,19 +37,27 @@ extern crate nix;
 
 extern crate bytemuck;
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
 pub(crate) mod util;
 
 pub mod buffer;
 pub mod control;
+pub mod node;
 
 use std::ffi::{OsStr, OsString};
+use std::io;
 use std::os::unix::{
     ffi::OsStringExt,
     io::{AsFd, AsRawFd},
 };
+use std::path::PathBuf;
 use std::time::Duration;
 
 pub use drm_ffi::result::SystemError;
+pub use node::enumerate_cards;
 use util::*;
 
 /// This trait should be implemented by any object that acts as a DRM device. It
@@ -155,6 +164,32 @@ pub trait Device: AsFd {
         Ok(bus_id)
     }
 
+    /// Gets the location of this device on its bus, parsed from the bus ID.
+    ///
+    /// Useful to match a device to its other nodes, or to a Vulkan physical
+    /// device by its PCI address.
+    fn get_device_info(&self) -> Result<BusInfo, SystemError> {
+        Ok(BusInfo::parse(&self.get_bus_id()?))
+    }
+
+    /// Returns the path of the render node of this device.
+    ///
+    /// Lets an application opened on a primary node do its rendering and
+    /// buffer allocation on the unprivileged render node, keeping the primary
+    /// node for scanout. Returns [`None`] if the driver provides no render
+    /// node. See [`node::node_path_for`].
+    fn render_node_path(&self) -> io::Result<Option<PathBuf>> {
+        node::node_path_for(self.as_fd(), node::NodeType::Render)
+    }
+
+    /// Returns the path of the primary node of this device, e.g. to find the
+    /// node used for mode setting from a render node.
+    ///
+    /// See [`node::node_path_for`].
+    fn primary_node_path(&self) -> io::Result<Option<PathBuf>> {
+        node::node_path_for(self.as_fd(), node::NodeType::Primary)
+    }
+
     /// Check to see if our [`AuthToken`] has been authenticated
     /// by the DRM Master
     fn authenticated(&self) -> Result<bool, SystemError> {
@@ -192,7 +227,54 @@ pub trait Device: AsFd {
         Ok(driver)
     }
 
+    /// Sets the name of this client, which is shown in debugfs and in the
+    /// fdinfo of the file, e.g. to tell which process holds the display.
+    ///
+    /// An empty name clears it. Returns [`SystemError::InvalidArgument`] if
+    /// the name is longer than 64 bytes or contains characters other than
+    /// printable ASCII without spaces, and [`SystemError::Unsupported`] if the
+    /// kernel is too old to support client names.
+    fn set_client_name(&self, name: &str) -> Result<(), SystemError> {
+        if name.len() > 64 || !name.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(SystemError::InvalidArgument);
+        }
+
+        // Kernels without the ioctl reject it as an invalid argument, as the
+        // name itself has already been checked.
+        match drm_ffi::set_client_name(self.as_fd().as_raw_fd(), name.as_bytes()) {
+            Err(SystemError::InvalidArgument) => Err(SystemError::Unsupported),
+            result => result,
+        }
+    }
+
+    /// Issues an ioctl the crate does not wrap, e.g. a driver specific one.
+    ///
+    /// `request` is the full request number, e.g. as built by
+    /// [`nix::request_code_readwrite`]. Errors reported by the kernel are
+    /// converted to a [`SystemError`].
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the argument type the kernel expects for `request`, and
+    /// `arg` must be valid for every pointer it contains, as the kernel reads
+    /// and writes through them.
+    unsafe fn raw_ioctl<T>(&self, request: u64, arg: &mut T) -> Result<(), SystemError> {
+        use drm_ffi::ioctl::IoctlBackend;
+
+        self.as_fd()
+            .as_raw_fd()
+            .ioctl(request as _, arg as *mut T as *mut _)?;
+        Ok(())
+    }
+
     /// Waits for a vblank.
+    ///
+    /// `high_crtc` is the pipe index of the CRTC, i.e. its position in the
+    /// list of CRTCs of the device, not its object id. It is encoded into the
+    /// request type shifted by `_DRM_VBLANK_HIGH_CRTC_SHIFT`, which the kernel
+    /// understands for all pipes if
+    /// [`DriverCapability::VBlankHighCRTC`] is supported. Use
+    /// [`control::Device::wait_crtc_vblank`] to wait for a CRTC by its handle.
     fn wait_vblank(
         &self,
         target_sequence: VblankWaitTarget,
@@ -274,6 +356,53 @@ impl Driver {
     }
 }
 
+/// Location of a device on its bus, see [`Device::get_device_info`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum BusInfo {
+    /// A PCI device
+    Pci {
+        /// PCI domain
+        domain: u16,
+        /// Bus number
+        bus: u8,
+        /// Device number
+        device: u8,
+        /// Function number
+        function: u8,
+    },
+    /// A device on another bus, e.g. a platform device, along with its raw
+    /// bus ID
+    Other(OsString),
+}
+
+impl BusInfo {
+    /// Parses a bus ID as returned by [`Device::get_bus_id`].
+    ///
+    /// PCI devices are reported either as `pci:0000:01:00.0` or, by newer
+    /// kernels, as `0000:01:00.0`.
+    pub fn parse(bus_id: &OsStr) -> BusInfo {
+        let pci = bus_id.to_str().and_then(|id| {
+            let id = id.strip_prefix("pci:").unwrap_or(id);
+            let mut parts = id.split(':');
+            let domain = u16::from_str_radix(parts.next()?, 16).ok()?;
+            let bus = u8::from_str_radix(parts.next()?, 16).ok()?;
+            let (device, function) = parts.next()?.split_once('.')?;
+            if parts.next().is_some() {
+                return None;
+            }
+
+            Some(BusInfo::Pci {
+                domain,
+                bus,
+                device: u8::from_str_radix(device, 16).ok()?,
+                function: u8::from_str_radix(function, 16).ok()?,
+            })
+        });
+
+        pci.unwrap_or_else(|| BusInfo::Other(bus_id.to_owned()))
+    }
+}
+
 /// Used to check which capabilities your graphics driver has.
 #[allow(clippy::upper_case_acronyms)]
 #[repr(u64)]
@@ -285,11 +414,13 @@ pub enum DriverCapability {
     VBlankHighCRTC = drm_ffi::DRM_CAP_VBLANK_HIGH_CRTC as u64,
     /// Preferred depth to use for dumb buffers
     DumbPreferredDepth = drm_ffi::DRM_CAP_DUMB_PREFERRED_DEPTH as u64,
-    /// Unknown
+    /// Dumb buffers are slow to read from, so rendering into a shadow buffer
+    /// is preferred. Drivers reporting this are often virtual, see
+    /// [`control::Device::flush_framebuffer`].
     DumbPreferShadow = drm_ffi::DRM_CAP_DUMB_PREFER_SHADOW as u64,
     /// PRIME handles are supported
     Prime = drm_ffi::DRM_CAP_PRIME as u64,
-    /// Unknown
+    /// Event timestamps use `CLOCK_MONOTONIC` instead of `CLOCK_REALTIME`
     MonotonicTimestamp = drm_ffi::DRM_CAP_TIMESTAMP_MONOTONIC as u64,
     /// Asynchronous page flipping support
     ASyncPageFlip = drm_ffi::DRM_CAP_ASYNC_PAGE_FLIP as u64,
@@ -305,6 +436,10 @@ pub enum DriverCapability {
     CRTCInVBlankEvent = drm_ffi::DRM_CAP_CRTC_IN_VBLANK_EVENT as u64,
     /// SyncObj support
     SyncObj = drm_ffi::DRM_CAP_SYNCOBJ as u64,
+    /// Asynchronous page flipping support in atomic commits, see
+    /// [`control::AtomicCommitFlags::PAGE_FLIP_ASYNC`]
+    // `DRM_CAP_ATOMIC_ASYNC_PAGE_FLIP`, missing from the bundled bindings
+    AtomicASyncPageFlip = 0x15,
 }
 
 /// Used to enable/disable capabilities for the process.
@@ -317,6 +452,9 @@ pub enum ClientCapability {
     UniversalPlanes = drm_ffi::DRM_CLIENT_CAP_UNIVERSAL_PLANES as u64,
     /// The driver provides atomic modesetting
     Atomic = drm_ffi::DRM_CLIENT_CAP_ATOMIC as u64,
+    /// Modes include their picture aspect ratio, see
+    /// [`control::Mode::picture_aspect`]
+    AspectRatio = drm_ffi::DRM_CLIENT_CAP_ASPECT_RATIO as u64,
 }
 
 /// Used to specify a vblank sequence to wait for
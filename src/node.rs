@@ -0,0 +1,228 @@
+//! # Node
+//!
+//! Helpers to open DRM device nodes.
+//!
+//! This crate does not provide a concrete [`Device`](crate::Device). The
+//! files returned by these functions are meant to be wrapped by a type
+//! implementing it, as shown in the example of the [`Device`](crate::Device)
+//! trait.
+//!
+//! Devices are opened with `O_RDWR | O_CLOEXEC | O_NONBLOCK`. Because of
+//! `O_NONBLOCK`, reading events with
+//! [`control::Device::receive_events`](crate::control::Device::receive_events)
+//! fails with `EAGAIN` if no event is pending. Use
+//! [`control::Device::wait_events`](crate::control::Device::wait_events) to
+//! block until events arrive.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsFd, AsRawFd};
+use std::path::{Path, PathBuf};
+
+use util::transmute_vec;
+
+/// Directory containing the DRM device nodes on Linux
+const DRI_DIR: &str = "/dev/dri";
+
+/// The kind of a DRM device node
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum NodeType {
+    /// A primary node (`cardN`), which provides mode setting and can become the
+    /// DRM master.
+    Primary,
+    /// A render node (`renderDN`), which only provides rendering and buffer
+    /// sharing, but does not require authentication.
+    Render,
+}
+
+impl NodeType {
+    /// Returns the file name prefix of nodes of this type.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            NodeType::Primary => "card",
+            NodeType::Render => "renderD",
+        }
+    }
+}
+
+/// Opens a DRM device node and verifies that it is one.
+///
+/// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the file is not
+/// a DRM device. Errors from opening the file, e.g. due to missing permissions,
+/// mention the path of the node.
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    let path = path.as_ref();
+    let with_path =
+        |err: io::Error| io::Error::new(err.kind(), format!("{}: {}", path.display(), err));
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(nix::libc::O_CLOEXEC | nix::libc::O_NONBLOCK)
+        .open(path)
+        .map_err(with_path)?;
+
+    if drm_ffi::get_version(file.as_raw_fd(), None, None, None).is_err() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: not a DRM device node", path.display()),
+        ));
+    }
+
+    Ok(file)
+}
+
+/// Opens the first primary node found in `/dev/dri`.
+pub fn open_primary() -> io::Result<File> {
+    open_first(NodeType::Primary)
+}
+
+/// Opens the first render node found in `/dev/dri`.
+pub fn open_render_node() -> io::Result<File> {
+    open_first(NodeType::Render)
+}
+
+/// Returns the paths of all nodes of a type in `/dev/dri`, ordered by their
+/// minor number.
+pub fn node_paths(ty: NodeType) -> io::Result<Vec<PathBuf>> {
+    let mut nodes = Vec::new();
+
+    for entry in fs::read_dir(DRI_DIR)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let minor = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(ty.prefix()))
+            .and_then(|minor| minor.parse::<u32>().ok());
+
+        if let Some(minor) = minor {
+            nodes.push((minor, entry.path()));
+        }
+    }
+
+    nodes.sort();
+    Ok(nodes.into_iter().map(|(_, path)| path).collect())
+}
+
+/// A DRM device node found by [`enumerate_cards`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardPath {
+    pub(crate) path: PathBuf,
+    pub(crate) node_type: NodeType,
+    pub(crate) driver: OsString,
+}
+
+impl CardPath {
+    /// Returns the path of the node.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the type of the node.
+    pub fn node_type(&self) -> NodeType {
+        self.node_type
+    }
+
+    /// Returns the name of the driver of the device, e.g. `i915` or `amdgpu`.
+    pub fn driver(&self) -> &OsString {
+        &self.driver
+    }
+}
+
+/// Lists the primary and render nodes in `/dev/dri` along with the names of
+/// their drivers.
+///
+/// Every node is opened briefly to query its driver, which does not require
+/// becoming the DRM master. Nodes that cannot be opened due to missing
+/// permissions are skipped. Primary nodes are listed before render nodes,
+/// each ordered by their minor number.
+pub fn enumerate_cards() -> io::Result<Vec<CardPath>> {
+    let mut cards = Vec::new();
+
+    for &node_type in &[NodeType::Primary, NodeType::Render] {
+        for path in node_paths(node_type)? {
+            let file = match open(&path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::PermissionDenied => continue,
+                Err(err) => return Err(err),
+            };
+
+            let mut name = Vec::new();
+            drm_ffi::get_version(file.as_raw_fd(), Some(&mut name), None, None)
+                .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+            let driver = OsString::from_vec(unsafe { transmute_vec(name) });
+
+            cards.push(CardPath {
+                path,
+                node_type,
+                driver,
+            });
+        }
+    }
+
+    Ok(cards)
+}
+
+/// Returns the path of the node of a type belonging to the same device as an
+/// open node, e.g. the render node matching a primary node.
+///
+/// The node is looked up in sysfs by the device number of `fd`. Returns
+/// [`None`] if the device has no node of the requested type, as some drivers
+/// do not provide render nodes.
+pub fn node_path_for<F: AsFd>(fd: F, ty: NodeType) -> io::Result<Option<PathBuf>> {
+    use nix::sys::stat::{fstat, major, minor, SFlag};
+
+    let stat = fstat(fd.as_fd().as_raw_fd())?;
+    if SFlag::from_bits_truncate(stat.st_mode) & SFlag::S_IFMT != SFlag::S_IFCHR {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a character device",
+        ));
+    }
+
+    let dir = format!(
+        "/sys/dev/char/{}:{}/device/drm",
+        major(stat.st_rdev),
+        minor(stat.st_rdev)
+    );
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    for entry in entries {
+        let name = entry?.file_name();
+        let is_node = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(ty.prefix()))
+            .map_or(false, |minor| minor.parse::<u32>().is_ok());
+
+        if is_node {
+            return Ok(Some(Path::new(DRI_DIR).join(name)));
+        }
+    }
+
+    Ok(None)
+}
+
+fn open_first(ty: NodeType) -> io::Result<File> {
+    let mut last_err = None;
+
+    for path in node_paths(ty)? {
+        match open(&path) {
+            Ok(file) => return Ok(file),
+            Err(err) => last_err = Some(err),
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no {} node found in {}", ty.prefix(), DRI_DIR),
+        )
+    }))
+}
@@ -1,6 +1,9 @@
 //! Utilities used internally by this crate.
 
 use crate::control::{from_u32, RawResourceHandle};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 pub unsafe fn transmute_vec<T, U>(from: Vec<T>) -> Vec<U> {
     let mut from = std::mem::ManuallyDrop::new(from);
@@ -17,3 +20,41 @@ pub unsafe fn transmute_vec_from_u32<T: From<RawResourceHandle>>(raw: Vec<u32>)
         transmute_vec(raw)
     }
 }
+
+/// A map behind a mutex, used by the caches of this crate so that they can
+/// be shared between threads
+#[derive(Debug)]
+pub struct SharedMap<K, V>(Mutex<HashMap<K, V>>);
+
+impl<K, V> Default for SharedMap<K, V> {
+    fn default() -> Self {
+        SharedMap(Mutex::new(HashMap::new()))
+    }
+}
+
+impl<K, V> SharedMap<K, V> {
+    pub fn lock(&self) -> MutexGuard<'_, HashMap<K, V>> {
+        // Entries are only ever inserted or overwritten as a whole, so the map
+        // is never left in an inconsistent state
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> SharedMap<K, V> {
+    /// Returns the value of `key`, calling `f` to compute it on a miss.
+    pub fn get_or_try_insert_with<E, F>(&self, key: K, f: F) -> Result<V, E>
+    where
+        F: FnOnce() -> Result<V, E>,
+    {
+        if let Some(value) = self.lock().get(&key) {
+            return Ok(value.clone());
+        }
+
+        // Do not hold the lock while computing the value, which usually takes
+        // ioctls. If another thread misses on the same key meanwhile, both
+        // store the same value.
+        let value = f()?;
+        self.lock().insert(key, value.clone());
+        Ok(value)
+    }
+}